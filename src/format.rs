@@ -2,19 +2,116 @@
 #![allow(non_snake_case)]
 
 use from_json;
-use std::collections::HashMap;
+use rustc_serialize::json::{Json, ToJson};
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::mem;
+
+macro_rules! json_key {
+    ($field:ident) => (stringify!($field));
+    ($field:ident as $json:expr) => ($json);
+}
+
+/// Implements `ToJson` for a struct, with the same syntax as `derive_from_json!`.
+///
+/// The members whose value is `null` are omitted.
+macro_rules! derive_to_json {
+    ($t:ident, $($field:ident $(as $json:expr)*),+) => (
+        impl ToJson for $t {
+            fn to_json(&self) -> Json {
+                let mut object = BTreeMap::new();
+                $(
+                    let value = self.$field.to_json();
+                    if value != Json::Null {
+                        object.insert(json_key!($field $(as $json)*).to_string(), value);
+                    }
+                )+
+                Json::Object(object)
+            }
+        }
+    )
+}
+
+/// Estimates the number of bytes allocated on the heap by a value.
+pub trait HeapSize {
+    fn heap_size(&self) -> usize;
+}
+
+macro_rules! impl_heap_size {
+    ($t:ident, $($field:ident),+) => (
+        impl HeapSize for $t {
+            fn heap_size(&self) -> usize {
+                0 $(+ self.$field.heap_size())+
+            }
+        }
+    )
+}
+
+impl HeapSize for i32 {
+    fn heap_size(&self) -> usize { 0 }
+}
+
+impl HeapSize for f64 {
+    fn heap_size(&self) -> usize { 0 }
+}
+
+impl HeapSize for bool {
+    fn heap_size(&self) -> usize { 0 }
+}
 
-#[derive(Debug, Clone)]
+impl HeapSize for String {
+    fn heap_size(&self) -> usize { self.capacity() }
+}
+
+impl<T: HeapSize> HeapSize for Option<T> {
+    fn heap_size(&self) -> usize {
+        self.as_ref().map(|v| v.heap_size()).unwrap_or(0)
+    }
+}
+
+impl<T: HeapSize> HeapSize for Vec<T> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * mem::size_of::<T>() + self.iter().map(|v| v.heap_size())
+                                                       .fold(0, |a, b| a + b)
+    }
+}
+
+impl<K: HeapSize + Eq + Hash, V: HeapSize> HeapSize for HashMap<K, V> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * (mem::size_of::<K>() + mem::size_of::<V>()) +
+            self.iter().map(|(k, v)| k.heap_size() + v.heap_size()).fold(0, |a, b| a + b)
+    }
+}
+
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
 pub struct Document {
+    pub skeleton: Option<Skeleton>,
     pub bones: Option<Vec<Bone>>,
     pub slots: Option<Vec<Slot>>,
     pub skins: Option<HashMap<String, HashMap<String, HashMap<String, Attachment>>>>,
+    pub events: Option<HashMap<String, Event>>,
     pub animations: Option<HashMap<String, Animation>>,
 }
 
-derive_from_json!(Document, bones, slots, skins, animations);
+derive_from_json!(Document, skeleton, bones, slots, skins, events, animations);
+derive_to_json!(Document, skeleton, bones, slots, skins, events, animations);
+impl_heap_size!(Document, skeleton, bones, slots, skins, events, animations);
+
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct Skeleton {
+    pub hash: Option<String>,
+    pub spine: Option<String>,
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+    pub images: Option<String>,
+    pub fps: Option<f64>,
+}
+
+derive_from_json!(Skeleton, hash, spine, width, height, images, fps);
+derive_to_json!(Skeleton, hash, spine, width, height, images, fps);
+impl_heap_size!(Skeleton, hash, spine, width, height, images, fps);
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
 pub struct Bone {
     pub name: String,
     pub parent: Option<String>,
@@ -24,11 +121,18 @@ pub struct Bone {
     pub scaleX: Option<f64>,
     pub scaleY: Option<f64>,
     pub rotation: Option<f64>,
+    pub inheritScale: Option<bool>,
+    pub inheritRotation: Option<bool>,
 }
 
-derive_from_json!(Bone, name, parent, length, x, y, scaleX, scaleY, rotation);
+derive_from_json!(Bone, name, parent, length, x, y, scaleX, scaleY, rotation, inheritScale,
+                  inheritRotation);
+derive_to_json!(Bone, name, parent, length, x, y, scaleX, scaleY, rotation, inheritScale,
+                inheritRotation);
+impl_heap_size!(Bone, name, parent, length, x, y, scaleX, scaleY, rotation, inheritScale,
+                inheritRotation);
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
 pub struct Slot {
     pub name: String,
     pub bone: String,
@@ -37,10 +141,13 @@ pub struct Slot {
 }
 
 derive_from_json!(Slot, name, bone, color, attachment);
+derive_to_json!(Slot, name, bone, color, attachment);
+impl_heap_size!(Slot, name, bone, color, attachment);
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
 pub struct Attachment {
     pub name: Option<String>,
+    pub path: Option<String>,
     pub type_: Option<AttachmentType>,
     pub x: Option<f64>,
     pub y: Option<f64>,
@@ -50,18 +157,65 @@ pub struct Attachment {
     pub width: Option<f64>,
     pub height: Option<f64>,
     pub fps: Option<f64>,
-    pub mode: Option<f64>,
-    //vertices: Option<Vec<??>>     // TODO: ?
+    pub mode: Option<RegionSequenceMode>,
+    pub sequence: Option<Sequence>,
+    pub vertexCount: Option<i32>,
+    pub vertices: Option<Vec<f64>>,
+    pub lengths: Option<Vec<f64>>,
+    pub closed: Option<bool>,
+    pub constantSpeed: Option<bool>,
+    pub uvs: Option<Vec<f64>>,
+    pub triangles: Option<Vec<i32>>,
+    pub hull: Option<i32>,
+    pub parent: Option<String>,
+    pub skin: Option<String>,
+    pub deform: Option<bool>,
 }
 
-derive_from_json!(Attachment, name, type_ as "type", x, y, scaleX, scaleY, rotation, width, height,
-                  fps, mode);
+derive_from_json!(Attachment, name, path, type_ as "type", x, y, scaleX, scaleY, rotation, width, height,
+                  fps, mode, sequence, vertexCount, vertices, lengths, closed, constantSpeed, uvs,
+                  triangles, hull, parent, skin, deform);
+derive_to_json!(Attachment, name, path, type_ as "type", x, y, scaleX, scaleY, rotation, width, height,
+                fps, mode, sequence, vertexCount, vertices, lengths, closed, constantSpeed, uvs,
+                triangles, hull, parent, skin, deform);
+impl_heap_size!(Attachment, name, path, type_, x, y, scaleX, scaleY, rotation, width, height, fps,
+                mode, sequence, vertexCount, vertices, lengths, closed, constantSpeed, uvs,
+                triangles, hull, parent, skin, deform);
+
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct Sequence {
+    pub count: i32,
+    pub start: Option<i32>,
+    pub digits: Option<i32>,
+    pub setup: Option<i32>,
+}
+
+derive_from_json!(Sequence, count, start, digits, setup);
+derive_to_json!(Sequence, count, start, digits, setup);
+impl_heap_size!(Sequence, count, start, digits, setup);
 
-#[derive(Debug, Clone)]
+/// Type of an attachment.
+///
+/// Only regions and region sequences are drawn as textured rectangles by this library. The
+///  other types are returned as they are, so that you can skip them or handle them yourself.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, RustcEncodable, RustcDecodable)]
 pub enum AttachmentType {
+    /// A textured rectangle.
     Region,
+    /// A textured rectangle whose texture changes over time.
     RegionSequence,
+    /// A polygon used for hit detection, which is not supposed to be drawn.
     BoundingBox,
+    /// A textured polygon that can be deformed.
+    Mesh,
+    /// A mesh that shares the geometry of another mesh.
+    LinkedMesh,
+    /// A curve that bones can be constrained to follow.
+    Path,
+    /// A single point with a rotation, for example to spawn particles.
+    Point,
+    /// A polygon that clips the slots drawn after it.
+    Clipping,
 }
 
 impl from_json::FromJson for AttachmentType {
@@ -74,32 +228,113 @@ impl from_json::FromJson for AttachmentType {
             "region" => Ok(AttachmentType::Region),
             "regionsequence" => Ok(AttachmentType::RegionSequence),
             "boundingbox" => Ok(AttachmentType::BoundingBox),
+            "mesh" => Ok(AttachmentType::Mesh),
+            "linkedmesh" => Ok(AttachmentType::LinkedMesh),
+            "path" => Ok(AttachmentType::Path),
+            "point" => Ok(AttachmentType::Point),
+            "clipping" => Ok(AttachmentType::Clipping),
             _ => Err(from_json::FromJsonError::ExpectError("AttachmentType", input.clone()))
         }
     }
 }
 
-#[derive(Debug, Clone)]
+impl ToJson for AttachmentType {
+    fn to_json(&self) -> Json {
+        match self {
+            &AttachmentType::Region => "region".to_json(),
+            &AttachmentType::RegionSequence => "regionsequence".to_json(),
+            &AttachmentType::BoundingBox => "boundingbox".to_json(),
+            &AttachmentType::Mesh => "mesh".to_json(),
+            &AttachmentType::LinkedMesh => "linkedmesh".to_json(),
+            &AttachmentType::Path => "path".to_json(),
+            &AttachmentType::Point => "point".to_json(),
+            &AttachmentType::Clipping => "clipping".to_json(),
+        }
+    }
+}
+
+impl HeapSize for AttachmentType {
+    fn heap_size(&self) -> usize { 0 }
+}
+
+/// How the regions of a legacy region sequence attachment are played.
+///
+/// Stored as a number in the documents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, RustcEncodable, RustcDecodable)]
+pub enum RegionSequenceMode {
+    Forward,
+    Backward,
+    ForwardLoop,
+    BackwardLoop,
+    PingPong,
+    Random,
+}
+
+impl from_json::FromJson for RegionSequenceMode {
+    fn from_json(input: &from_json::Json)
+        -> Result<RegionSequenceMode, from_json::FromJsonError>
+    {
+        use from_json::FromJson;
+
+        let number: f64 = try!(FromJson::from_json(input));
+
+        match number {
+            0.0 => Ok(RegionSequenceMode::Forward),
+            1.0 => Ok(RegionSequenceMode::Backward),
+            2.0 => Ok(RegionSequenceMode::ForwardLoop),
+            3.0 => Ok(RegionSequenceMode::BackwardLoop),
+            4.0 => Ok(RegionSequenceMode::PingPong),
+            5.0 => Ok(RegionSequenceMode::Random),
+            _ => Err(from_json::FromJsonError::ExpectError("RegionSequenceMode", input.clone()))
+        }
+    }
+}
+
+impl ToJson for RegionSequenceMode {
+    fn to_json(&self) -> Json {
+        match self {
+            &RegionSequenceMode::Forward => 0.to_json(),
+            &RegionSequenceMode::Backward => 1.to_json(),
+            &RegionSequenceMode::ForwardLoop => 2.to_json(),
+            &RegionSequenceMode::BackwardLoop => 3.to_json(),
+            &RegionSequenceMode::PingPong => 4.to_json(),
+            &RegionSequenceMode::Random => 5.to_json(),
+        }
+    }
+}
+
+impl HeapSize for RegionSequenceMode {
+    fn heap_size(&self) -> usize { 0 }
+}
+
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
 pub struct Event {
-    pub name: String,
     pub int_: Option<i32>,
     pub float_: Option<f64>,
     pub string: Option<String>,
+    pub audio: Option<String>,
+    pub volume: Option<f64>,
+    pub balance: Option<f64>,
 }
 
-derive_from_json!(Event, name, int_ as "int", float_ as "float", string);
+derive_from_json!(Event, int_ as "int", float_ as "float", string, audio, volume, balance);
+derive_to_json!(Event, int_ as "int", float_ as "float", string, audio, volume, balance);
+impl_heap_size!(Event, int_, float_, string, audio, volume, balance);
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
 pub struct Animation {
     pub bones: Option<HashMap<String, BoneTimeline>>,
     pub slots: Option<HashMap<String, SlotTimeline>>,
+    pub attachments: Option<HashMap<String, HashMap<String, HashMap<String, AttachmentTimeline>>>>,
     pub events: Option<Vec<EventKeyframe>>,
     pub draworder: Option<Vec<DrawOrderTimeline>>,
 }
 
-derive_from_json!(Animation, bones, slots, events, draworder);
+derive_from_json!(Animation, bones, slots, attachments, events, draworder);
+derive_to_json!(Animation, bones, slots, attachments, events, draworder);
+impl_heap_size!(Animation, bones, slots, attachments, events, draworder);
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
 pub struct BoneTimeline {
     pub translate: Option<Vec<BoneTranslateTimeline>>,
     pub rotate: Option<Vec<BoneRotateTimeline>>,
@@ -107,8 +342,10 @@ pub struct BoneTimeline {
 }
 
 derive_from_json!(BoneTimeline, translate, rotate, scale);
+derive_to_json!(BoneTimeline, translate, rotate, scale);
+impl_heap_size!(BoneTimeline, translate, rotate, scale);
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
 pub struct BoneTranslateTimeline {
     pub time: f64,
     pub curve: Option<TimelineCurve>,
@@ -117,8 +354,10 @@ pub struct BoneTranslateTimeline {
 }
 
 derive_from_json!(BoneTranslateTimeline, time, curve, x, y);
+derive_to_json!(BoneTranslateTimeline, time, curve, x, y);
+impl_heap_size!(BoneTranslateTimeline, time, curve, x, y);
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
 pub struct BoneRotateTimeline {
     pub time: f64,
     pub curve: Option<TimelineCurve>,
@@ -126,8 +365,10 @@ pub struct BoneRotateTimeline {
 }
 
 derive_from_json!(BoneRotateTimeline, time, curve, angle);
+derive_to_json!(BoneRotateTimeline, time, curve, angle);
+impl_heap_size!(BoneRotateTimeline, time, curve, angle);
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
 pub struct BoneScaleTimeline {
     pub time: f64,
     pub curve: Option<TimelineCurve>,
@@ -136,8 +377,10 @@ pub struct BoneScaleTimeline {
 }
 
 derive_from_json!(BoneScaleTimeline, time, curve, x, y);
+derive_to_json!(BoneScaleTimeline, time, curve, x, y);
+impl_heap_size!(BoneScaleTimeline, time, curve, x, y);
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
 pub enum TimelineCurve {
     CurveBezier(Vec<f64>),
     CurvePredefined(String),
@@ -155,55 +398,203 @@ impl from_json::FromJson for TimelineCurve {
     }
 }
 
-#[derive(Debug, Clone)]
+impl ToJson for TimelineCurve {
+    fn to_json(&self) -> Json {
+        match self {
+            &TimelineCurve::CurveBezier(ref points) => points.to_json(),
+            &TimelineCurve::CurvePredefined(ref name) => name.to_json(),
+        }
+    }
+}
+
+impl HeapSize for TimelineCurve {
+    fn heap_size(&self) -> usize {
+        match self {
+            &TimelineCurve::CurveBezier(ref points) => points.heap_size(),
+            &TimelineCurve::CurvePredefined(ref name) => name.heap_size(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
 pub struct SlotTimeline {
     pub attachment: Option<Vec<SlotAttachmentTimeline>>,
     pub color: Option<Vec<SlotColorTimeline>>,
+    pub rgba: Option<Vec<SlotColorTimeline>>,
+    pub rgb: Option<Vec<SlotColorTimeline>>,
+    pub alpha: Option<Vec<SlotAlphaTimeline>>,
 }
 
-derive_from_json!(SlotTimeline, attachment, color);
+derive_from_json!(SlotTimeline, attachment, color, rgba, rgb, alpha);
+derive_to_json!(SlotTimeline, attachment, color, rgba, rgb, alpha);
+impl_heap_size!(SlotTimeline, attachment, color, rgba, rgb, alpha);
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
 pub struct SlotAttachmentTimeline {
     pub time: f64,
     pub name: Option<String>,
 }
 
 derive_from_json!(SlotAttachmentTimeline, time, name);
+derive_to_json!(SlotAttachmentTimeline, time, name);
+impl_heap_size!(SlotAttachmentTimeline, time, name);
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
 pub struct SlotColorTimeline {
-    pub time: f64,
+    pub time: Option<f64>,
     pub color: Option<String>,
     pub curve: Option<TimelineCurve>,
 }
 
 derive_from_json!(SlotColorTimeline, time, color, curve);
+derive_to_json!(SlotColorTimeline, time, color, curve);
+impl_heap_size!(SlotColorTimeline, time, color, curve);
+
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct SlotAlphaTimeline {
+    pub time: Option<f64>,
+    pub value: Option<f64>,
+    pub curve: Option<TimelineCurve>,
+}
+
+derive_from_json!(SlotAlphaTimeline, time, value, curve);
+derive_to_json!(SlotAlphaTimeline, time, value, curve);
+impl_heap_size!(SlotAlphaTimeline, time, value, curve);
+
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct AttachmentTimeline {
+    pub sequence: Option<Vec<SequenceKeyframe>>,
+}
+
+derive_from_json!(AttachmentTimeline, sequence);
+derive_to_json!(AttachmentTimeline, sequence);
+impl_heap_size!(AttachmentTimeline, sequence);
+
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct SequenceKeyframe {
+    pub time: f64,
+    pub mode: Option<SequenceMode>,
+    pub index: Option<i32>,
+    pub delay: Option<f64>,
+}
+
+derive_from_json!(SequenceKeyframe, time, mode, index, delay);
+derive_to_json!(SequenceKeyframe, time, mode, index, delay);
+impl_heap_size!(SequenceKeyframe, time, mode, index, delay);
+
+/// How the regions of a sequence are played after a keyframe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, RustcEncodable, RustcDecodable)]
+pub enum SequenceMode {
+    Hold,
+    Once,
+    Loop,
+    PingPong,
+    OnceReverse,
+    LoopReverse,
+    PingPongReverse,
+    Random,
+}
+
+impl from_json::FromJson for SequenceMode {
+    fn from_json(input: &from_json::Json) -> Result<SequenceMode, from_json::FromJsonError> {
+        use from_json::FromJson;
+
+        let string: String = try!(FromJson::from_json(input));
+
+        match &string[..] {
+            "hold" => Ok(SequenceMode::Hold),
+            "once" => Ok(SequenceMode::Once),
+            "loop" => Ok(SequenceMode::Loop),
+            "pingpong" => Ok(SequenceMode::PingPong),
+            "onceReverse" => Ok(SequenceMode::OnceReverse),
+            "loopReverse" => Ok(SequenceMode::LoopReverse),
+            "pingpongReverse" => Ok(SequenceMode::PingPongReverse),
+            "random" => Ok(SequenceMode::Random),
+            _ => Err(from_json::FromJsonError::ExpectError("SequenceMode", input.clone()))
+        }
+    }
+}
 
-#[derive(Debug, Clone)]
+impl ToJson for SequenceMode {
+    fn to_json(&self) -> Json {
+        match self {
+            &SequenceMode::Hold => "hold".to_json(),
+            &SequenceMode::Once => "once".to_json(),
+            &SequenceMode::Loop => "loop".to_json(),
+            &SequenceMode::PingPong => "pingpong".to_json(),
+            &SequenceMode::OnceReverse => "onceReverse".to_json(),
+            &SequenceMode::LoopReverse => "loopReverse".to_json(),
+            &SequenceMode::PingPongReverse => "pingpongReverse".to_json(),
+            &SequenceMode::Random => "random".to_json(),
+        }
+    }
+}
+
+impl HeapSize for SequenceMode {
+    fn heap_size(&self) -> usize { 0 }
+}
+
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
 pub struct EventKeyframe {
-    time: f64,
-    name: String,
-    int_: Option<i32>,
-    float_: Option<f64>,
-    string_: Option<String>,
+    pub time: f64,
+    pub name: String,
+    pub int_: Option<i32>,
+    pub float_: Option<f64>,
+    pub string_: Option<String>,
+    pub volume: Option<f64>,
+    pub balance: Option<f64>,
 }
 
 derive_from_json!(EventKeyframe, time, name, int_ as "int", float_ as "float",
-                  string_ as "string");
+                  string_ as "string", volume, balance);
+derive_to_json!(EventKeyframe, time, name, int_ as "int", float_ as "float",
+                string_ as "string", volume, balance);
+impl_heap_size!(EventKeyframe, time, name, int_, float_, string_, volume, balance);
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
 pub struct DrawOrderTimeline {
-    time: f64,
-    offsets: Option<Vec<DrawOrderTimelineOffset>>,
+    pub time: f64,
+    pub offsets: Option<Vec<DrawOrderTimelineOffset>>,
 }
 
 derive_from_json!(DrawOrderTimeline, time, offsets);
+derive_to_json!(DrawOrderTimeline, time, offsets);
+impl_heap_size!(DrawOrderTimeline, time, offsets);
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
 pub struct DrawOrderTimelineOffset {
-    slot: String,
-    offset: i32,
+    pub slot: String,
+    pub offset: i32,
 }
 
 derive_from_json!(DrawOrderTimelineOffset, slot, offset);
+derive_to_json!(DrawOrderTimelineOffset, slot, offset);
+impl_heap_size!(DrawOrderTimelineOffset, slot, offset);
+
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct SetupOverrides {
+    pub bones: Option<HashMap<String, BoneSetup>>,
+    pub slots: Option<HashMap<String, SlotSetup>>,
+}
+
+derive_from_json!(SetupOverrides, bones, slots);
+
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct BoneSetup {
+    pub length: Option<f64>,
+    pub x: Option<f64>,
+    pub y: Option<f64>,
+    pub scaleX: Option<f64>,
+    pub scaleY: Option<f64>,
+    pub rotation: Option<f64>,
+}
+
+derive_from_json!(BoneSetup, length, x, y, scaleX, scaleY, rotation);
+
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct SlotSetup {
+    pub color: Option<String>,
+    pub attachment: Option<String>,
+}
+
+derive_from_json!(SlotSetup, color, attachment);
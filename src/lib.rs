@@ -63,9 +63,9 @@ The results contain the list of sprites that need to be drawn, with their matrix
 # let results: spine::Calculation = unsafe { std::mem::uninitialized() };
 # let textures_list: HashMap<&str, i32> = unsafe { std::mem::uninitialized() };
 # fn draw<A,B,C>(_: A, _: B, _: C) {}
-for (sprite_name, matrix, color) in results.sprites.into_iter() {
-    let texture = textures_list.get(&sprite_name).unwrap();
-    draw(texture, matrix, color);
+for sprite in results.sprites.into_iter() {
+    let texture = textures_list.get(&sprite.name).unwrap();
+    draw(texture, sprite.matrix, sprite.color);
 }
 ```
 
@@ -76,399 +76,3799 @@ extern crate color;
 extern crate cgmath;
 #[macro_use]
 extern crate from_json;
+extern crate rustc_serialize;
+#[cfg(feature = "cache")]
+extern crate bincode;
 
 use color::{Rgb, Rgba};
 use cgmath::Matrix4;
 
-use std::io::Read;
+use std::cmp;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::mem;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
 mod format;
+mod state;
+
+pub use format::AttachmentType;
+pub use state::AnimationState;
+
+/// Name of an animation that animates nothing and lasts 0 seconds, available in every document.
+///
+/// Playing it gives the setup pose, and crossfading to it fades the previous animation out
+///  towards the setup pose. An animation of the document with the same name takes precedence.
+pub const EMPTY_ANIMATION: &'static str = "<empty>";
+
+/// Maximum number of frames calculated at once by `for_each_frame` and `calculate_flat_frames`,
+///  and of loops of an animation covered by `get_fired_events`.
+pub const MAX_FRAMES: usize = 100000;
+
+/// Maximum number of regions of a sequence.
+const MAX_SEQUENCE_COUNT: i32 = 10000;
+
+/// Start of the caches written by `write_cache`. Must be changed whenever the content of
+///  `format::Document` changes.
+#[cfg(feature = "cache")]
+const CACHE_HEADER: &'static [u8] = b"spine-rs cache 5\n";
 
 /// Spine document loaded in memory.
+///
+/// The document is `Send` and `Sync`, which means that you can load it in a background thread
+///  and share it between threads once it is loaded.
+///
+/// Cloning a document makes a deep copy of all its content, which costs as much memory as
+///  loading it again (see `get_memory_usage`) but is faster than parsing. Only clone it if
+///  you need to modify the copy, for example with `replace_animation`.
+#[derive(Clone)]
 pub struct SpineDocument {
     source: format::Document,
+
+    /// Names of the regions of each attachment that has a sequence, indexed by skin, slot and
+    ///  attachment.
+    sequences: HashMap<String, HashMap<String, HashMap<String, Vec<String>>>>,
+
+    /// Scale that was applied to the positions and lengths when loading the document.
+    scale: f64,
+
+    /// Animation returned by `get_animation` for `EMPTY_ANIMATION`.
+    empty_animation: format::Animation,
 }
 
 impl SpineDocument {
     /// Loads a document from a reader.
-    pub fn new<R: Read>(mut reader: R) -> Result<SpineDocument, String> {
-        let document = try!(from_json::Json::from_reader(&mut reader).map_err(|e| format!("{:?}", e)));
-        let document: format::Document = try!(from_json::FromJson::from_json(&document)
-            .map_err(|e| format!("{:?}", e)));
+    pub fn new<R: Read>(reader: R) -> Result<SpineDocument, LoadError> {
+        SpineDocument::new_scaled(reader, 1.0)
+    }
+
+    /// Loads a document from a reader and multiplies all its positions and lengths by `scale`.
+    ///
+    /// This is useful if the document was made with a different unit than the one you use
+    ///  when drawing. The positions and lengths of the bones, the translations of the
+    ///  animations and the positions and sizes of the attachments are scaled, but not the
+    ///  rotations nor the scales. The animations loaded later with `replace_animation` are
+    ///  scaled as well. Returns `InvalidScale` if `scale` is 0, infinite or NaN.
+    pub fn new_scaled<R: Read>(mut reader: R, scale: f32) -> Result<SpineDocument, LoadError> {
+        if scale == 0.0 || !scale.is_finite() {
+            return Err(LoadError::InvalidScale);
+        }
+
+        let json = try!(read_json(&mut reader));
+        SpineDocument::from_json_value(&json, scale)
+    }
+
+    /// Loads a document from a reader and returns it along with the JSON it was parsed from.
+    ///
+    /// The JSON contains everything that is in the document, including the elements that are
+    ///  not recognized by this library. This lets tools read them without parsing the document
+    ///  a second time.
+    pub fn new_with_json<R: Read>(mut reader: R)
+        -> Result<(SpineDocument, from_json::Json), LoadError>
+    {
+        let json = try!(read_json(&mut reader));
+        let document = try!(SpineDocument::from_json_value(&json, 1.0));
+        Ok((document, json))
+    }
+
+    /// Builds a document from parsed JSON.
+    fn from_json_value(json: &from_json::Json, scale: f32) -> Result<SpineDocument, LoadError> {
+        let mut document: format::Document = try!(from_json::FromJson::from_json(json)
+            .map_err(|e| LoadError::Format(format!("{:?}", e))));
+        if let Some(ref mut animations) = document.animations {
+            for (name, animation) in animations.iter_mut() {
+                try!(convert_channel_curves(name, animation, true));
+                merge_rgba_timelines(animation);
+            }
+        }
+        try!(check_numbers(&document));
+        try!(resolve_linked_meshes(&mut document));
+
+        let scale = scale as f64;
+        if scale != 1.0 {
+            scale_document(&mut document, scale);
+        }
+
+        let sequences = get_sequences_regions(&document);
 
         Ok(SpineDocument {
-            source: document
+            source: document,
+            sequences: sequences,
+            scale: scale,
+            empty_animation: empty_animation(),
         })
     }
 
-    /// Returns the list of all animations in this document.
-    pub fn get_animations_list(&self) -> Vec<&str> {
-        if let Some(ref list) = self.source.animations {
-            list.keys().map(|e| &e[..]).collect()
-        } else {
-            Vec::new()
+    /// Loads an animation from a reader and adds it to the document, replacing the existing
+    ///  animation with the same name if any.
+    ///
+    /// The reader must contain the JSON object of a single animation, ie. what is found in the
+    ///  `animations` member of a document. Returns an error if the animation references a bone or
+    ///  a slot that doesn't exist, in which case the document is left untouched.
+    pub fn replace_animation<R: Read>(&mut self, name: &str, reader: R)
+        -> Result<(), LoadError>
+    {
+        let animation = try!(self.load_animation(name, reader));
+
+        // checking that the bones and slots exist
+        if let Some(ref bones) = animation.bones {
+            for bone in bones.keys() {
+                if !self.bone_exists(bone) {
+                    return Err(LoadError::BoneNotFound(bone.clone()));
+                }
+            }
         }
-    }
 
-    /// Returns the list of all skins in this document.
-    pub fn get_skins_list(&self) -> Vec<&str> {
-        if let Some(ref list) = self.source.skins {
-            list.keys().map(|e| &e[..]).collect()
-        } else {
-            Vec::new()
+        {
+            let mut slots: Vec<&String> = Vec::new();
+            if let Some(ref timelines) = animation.slots {
+                slots.extend(timelines.keys());
+            }
+            if let Some(ref skins) = animation.attachments {
+                slots.extend(skins.values().flat_map(|s| s.keys()));
+            }
+            if let Some(ref draworder) = animation.draworder {
+                slots.extend(draworder.iter().flat_map(|d| d.offsets.iter())
+                                      .flat_map(|o| o.iter()).map(|o| &o.slot));
+            }
+
+            for slot in slots.into_iter() {
+                if !self.slot_exists(slot) {
+                    return Err(LoadError::SlotNotFound(slot.clone()));
+                }
+            }
         }
+
+        self.insert_animation(name, animation);
+        Ok(())
     }
 
-    /// Returns true if an animation is in the document.
-    pub fn has_animation(&self, name: &str) -> bool {
-        if let Some(ref list) = self.source.animations {
-            list.get(&name.to_string()).is_some()
-        } else {
-            false
-        }
+    /// Same as `replace_animation`, but the timelines of the bones and slots that don't exist
+    ///  are ignored instead of returning an error.
+    ///
+    /// This is useful for documents that were partially edited or made with another version
+    ///  of Spine. Returns a warning for each timeline that was ignored.
+    pub fn replace_animation_lenient<R: Read>(&mut self, name: &str, reader: R)
+        -> Result<Vec<String>, LoadError>
+    {
+        let mut animation = try!(self.load_animation(name, reader));
+        let warnings = self.remove_unknown_timelines(&mut animation);
+        self.insert_animation(name, animation);
+        Ok(warnings)
     }
 
-    /// Returns true if a skin is in the document.
-    pub fn has_skin(&self, name: &str) -> bool {
-        if let Some(ref list) = self.source.skins {
-            list.get(&name.to_string()).is_some()
-        } else {
-            false
+    /// Copies an animation from another document, for example from a character variant whose
+    ///  bones have the same names but a different setup pose.
+    ///
+    /// The timelines are relative to the setup pose, which means that the animation is played
+    ///  on top of the setup pose of this document. The timelines of the bones and slots that
+    ///  don't exist in this document are ignored, with a warning for each of them. The
+    ///  animation replaces the one with the same name, if any.
+    ///
+    /// Returns `None` if the animation doesn't exist in `source`.
+    pub fn retarget_animation(&mut self, source: &SpineDocument, animation: &str)
+        -> Option<Vec<String>>
+    {
+        let mut copy = match source.get_animation(animation) {
+            Some(a) => a.clone(),
+            None => return None
+        };
+
+        // the translations were scaled when loading the source document
+        if self.scale != source.scale {
+            scale_animation(&mut copy, self.scale / source.scale);
         }
+
+        let warnings = self.remove_unknown_timelines(&mut copy);
+        self.insert_animation(animation, copy);
+        Some(warnings)
     }
 
-    /// Returns the duration of an animation.
+    /// Loads a JSON object from a reader and patches the setup pose of the document with it.
     ///
-    /// Returns `None` if the animation doesn't exist.
-    /// 
-    /// TODO: check events and draworder?
-    pub fn get_animation_duration(&self, animation: &str) -> Option<f32> {
-        // getting a reference to the `format::Animation`
-        let animation: &format::Animation = 
-            if let Some(anim) = self.source.animations.as_ref() {
-                match anim.get(animation) {
-                    Some(a) => a,
-                    None => return None
+    /// The object has the form `{ "bones": { "name": { "x": 1, "rotation": 90 } },
+    ///  "slots": { "name": { "color": "ff0000ff" } } }`. A bone accepts the same members as
+    ///  in a document except `name` and `parent`, a slot accepts `color` and `attachment`, and
+    ///  the members that are not present are left unchanged. Positions and lengths are scaled
+    ///  like the ones of the document.
+    ///
+    /// If `lenient` is false, a bone or slot that doesn't exist is an error and the document
+    ///  is left untouched. Otherwise it is ignored with a warning. An invalid color is always
+    ///  an error.
+    pub fn apply_setup_overrides<R: Read>(&mut self, mut reader: R, lenient: bool)
+        -> Result<Vec<String>, LoadError>
+    {
+        let json = try!(read_json(&mut reader));
+        let overrides: format::SetupOverrides = try!(from_json::FromJson::from_json(&json)
+            .map_err(|e| LoadError::Format(format!("{:?}", e))));
+
+        // checking everything before modifying the document
+        let mut warnings = Vec::new();
+        if let Some(ref bones) = overrides.bones {
+            for (name, bone) in bones.iter() {
+                if ![bone.length, bone.x, bone.y, bone.scaleX, bone.scaleY, bone.rotation].iter()
+                    .all(|&v| is_finite(v))
+                {
+                    return Err(LoadError::InvalidNumber(format!("bone `{}`", name)));
                 }
-            } else {
-                return None;
+                if !self.bone_exists(name) {
+                    if !lenient { return Err(LoadError::BoneNotFound(name.clone())); }
+                    warnings.push(format!("bone `{}` not found", name));
+                }
+            }
+        }
+        if let Some(ref slots) = overrides.slots {
+            for (name, slot) in slots.iter() {
+                if let Some(ref color) = slot.color {
+                    if parse_color(color).is_err() {
+                        return Err(LoadError::Format(format!("invalid color `{}` of slot `{}`",
+                                                             color, name)));
+                    }
+                }
+                if !self.slot_exists(name) {
+                    if !lenient { return Err(LoadError::SlotNotFound(name.clone())); }
+                    warnings.push(format!("slot `{}` not found", name));
+                }
+            }
+        }
+
+        let scale = self.scale;
+        if let (Some(overrides), Some(bones)) = (overrides.bones, self.source.bones.as_mut()) {
+            for bone in bones.iter_mut() {
+                if let Some(o) = overrides.get(&bone.name) {
+                    if o.length.is_some() { bone.length = o.length.map(|v| v * scale); }
+                    if o.x.is_some() { bone.x = o.x.map(|v| v * scale); }
+                    if o.y.is_some() { bone.y = o.y.map(|v| v * scale); }
+                    if o.scaleX.is_some() { bone.scaleX = o.scaleX; }
+                    if o.scaleY.is_some() { bone.scaleY = o.scaleY; }
+                    if o.rotation.is_some() { bone.rotation = o.rotation; }
+                }
+            }
+        }
+        if let (Some(overrides), Some(slots)) = (overrides.slots, self.source.slots.as_mut()) {
+            for slot in slots.iter_mut() {
+                if let Some(o) = overrides.get(&slot.name) {
+                    if o.color.is_some() { slot.color = o.color.clone(); }
+                    if o.attachment.is_some() { slot.attachment = o.attachment.clone(); }
+                }
+            }
+        }
+
+        warnings.sort();
+        Ok(warnings)
+    }
+
+    /// Removes the timelines of the bones and slots that don't exist from an animation, and
+    ///  returns a warning for each of them.
+    fn remove_unknown_timelines(&self, animation: &mut format::Animation) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        {
+            let mut unknown_bone = |bone: &String| {
+                let exists = self.bone_exists(bone);
+                if !exists { warnings.push(format!("bone `{}` not found", bone)); }
+                !exists
             };
 
-        // this contains the final result
-        let mut result = 0.0f64;
+            if let Some(ref mut bones) = animation.bones {
+                let unknown: Vec<String> = bones.keys().filter(|b| unknown_bone(*b))
+                                                .map(|b| b.clone()).collect();
+                for bone in unknown.iter() { bones.remove(bone); }
+            }
+        }
 
-        // checking the bones
-        if let Some(ref bones) = animation.bones {
-            for timelines in bones.values() {
-                if let Some(ref translate) = timelines.translate.as_ref() {
-                    for elem in translate.iter() {
-                        if elem.time > result { result = elem.time }
-                    }
-                }
-                if let Some(ref rotate) = timelines.rotate.as_ref() {
-                    for elem in rotate.iter() {
-                        if elem.time > result { result = elem.time }
-                    }
+        {
+            let mut unknown_slot = |slot: &String| {
+                let exists = self.slot_exists(slot);
+                if !exists { warnings.push(format!("slot `{}` not found", slot)); }
+                !exists
+            };
+
+            if let Some(ref mut timelines) = animation.slots {
+                let unknown: Vec<String> = timelines.keys().filter(|s| unknown_slot(*s))
+                                                    .map(|s| s.clone()).collect();
+                for slot in unknown.iter() { timelines.remove(slot); }
+            }
+
+            if let Some(ref mut skins) = animation.attachments {
+                for (_, slots) in skins.iter_mut() {
+                    let unknown: Vec<String> = slots.keys().filter(|s| unknown_slot(*s))
+                                                    .map(|s| s.clone()).collect();
+                    for slot in unknown.iter() { slots.remove(slot); }
                 }
-                if let Some(ref scale) = timelines.scale.as_ref() {
-                    for elem in scale.iter() {
-                        if elem.time > result { result = elem.time }
+            }
+
+            if let Some(ref mut draworder) = animation.draworder {
+                for keyframe in draworder.iter_mut() {
+                    if let Some(ref mut offsets) = keyframe.offsets {
+                        offsets.retain(|o| !unknown_slot(&o.slot));
                     }
                 }
             }
         }
 
-        // checking the slots
-        if let Some(ref slots) = animation.slots {
-            for timelines in slots.values() {
-                if let Some(ref attachment) = timelines.attachment.as_ref() {
-                    for elem in attachment.iter() {
-                        if elem.time > result { result = elem.time }
-                    }
+        warnings
+    }
+
+    /// Parses an animation and scales it like the rest of the document.
+    fn load_animation<R: Read>(&self, name: &str, mut reader: R)
+        -> Result<format::Animation, LoadError>
+    {
+        let animation = try!(read_json(&mut reader));
+        let mut animation: format::Animation = try!(from_json::FromJson::from_json(&animation)
+            .map_err(|e| LoadError::Format(format!("{:?}", e))));
+        try!(convert_channel_curves(name, &mut animation, true));
+        merge_rgba_timelines(&mut animation);
+        try!(check_animation_numbers(name, &animation));
+
+        if self.scale != 1.0 {
+            scale_animation(&mut animation, self.scale);
+        }
+
+        Ok(animation)
+    }
+
+    /// Adds an animation to the document, replacing the existing animation with the same name.
+    fn insert_animation(&mut self, name: &str, animation: format::Animation) {
+        if self.source.animations.is_none() {
+            self.source.animations = Some(HashMap::new());
+        }
+        self.source.animations.as_mut().unwrap().insert(name.to_string(), animation);
+    }
+
+    /// Returns true if a bone is in the document.
+    fn bone_exists(&self, name: &str) -> bool {
+        self.source.bones.as_ref().map(|b| b.iter().any(|b| b.name == name)).unwrap_or(false)
+    }
+
+    /// Returns true if a slot is in the document.
+    fn slot_exists(&self, name: &str) -> bool {
+        self.source.slots.as_ref().map(|s| s.iter().any(|s| s.name == name)).unwrap_or(false)
+    }
+
+    /// Looks for mistakes in the document that don't prevent it from being loaded, and returns
+    ///  a description of each of them, sorted alphabetically.
+    ///
+    /// For now, this reports the region attachments whose width or height is missing or zero,
+    ///  which are never visible and are a common export mistake, and the slots of the skins
+    ///  that don't exist, which are ignored by the calculations.
+    pub fn validate(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        for (skin_name, skin) in self.source.skins.iter().flat_map(|s| s.iter()) {
+            for (slot_name, attachments) in skin.iter() {
+                if !self.slot_exists(slot_name) {
+                    warnings.push(format!("slot `{}` of skin `{}` not found", slot_name,
+                                          skin_name));
+                    continue;
                 }
-                if let Some(ref color) = timelines.color.as_ref() {
-                    for elem in color.iter() {
-                        if elem.time > result { result = elem.time }
+
+                for (name, attachment) in attachments.iter() {
+                    match attachment.type_ {
+                        None | Some(AttachmentType::Region) |
+                        Some(AttachmentType::RegionSequence) => (),
+                        _ => continue
+                    }
+
+                    if attachment.width.unwrap_or(0.0) == 0.0 ||
+                       attachment.height.unwrap_or(0.0) == 0.0
+                    {
+                        warnings.push(format!("attachment `{}` of slot `{}` in skin `{}` has \
+                                               no area", name, slot_name, skin_name));
                     }
                 }
             }
         }
 
-        // returning
-        Some(result as f32)
+        warnings.sort();
+        warnings
     }
 
-    /// Returns a list of all possible sprites when drawing.
+    /// Returns a human-readable description of the document, for debugging purposes.
     ///
-    /// The purpose of this function is to allow you to preload what you need.
-    pub fn get_possible_sprites(&self) -> Vec<&str> {
-        if let Some(ref list) = self.source.skins {
-            let mut result = list.iter().flat_map(|(_, skin)| skin.iter())
-                                 .flat_map(|(_, slot)| slot.iter())
-                                 .map(|(name, vals)| {
-                                     if let Some(ref name) = vals.name {
-                                         &name[..]
-                                     } else {
-                                         &name[..]
-                                     }
-                                 })
-                                 .collect::<Vec<_>>();
+    /// The first line contains the number of bones, slots, skins and animations. It is
+    ///  followed by one line per animation, sorted by name, with its duration and the bones
+    ///  and slots that it animates.
+    pub fn get_summary(&self) -> String {
+        let mut summary = format!("{} bones, {} slots, {} skins, {} animations\n",
+                                  self.get_bones_list().len(), self.get_slots_list().len(),
+                                  self.get_skins_list().len(), self.get_animations_count());
 
-            result.sort();
-            result.dedup();
-            result
+        let mut animations = self.get_animations_list();
+        animations.sort();
 
-        } else {
-            Vec::new()
+        for name in animations.into_iter() {
+            let animation = self.get_animation(name).unwrap();
+
+            let mut bones: Vec<&str> = animation.bones.iter().flat_map(|b| b.keys())
+                                                .map(|b| &b[..]).collect();
+            bones.sort();
+
+            let mut slots: Vec<&str> = animation.slots.iter().flat_map(|s| s.keys())
+                                                .map(|s| &s[..]).collect();
+            slots.extend(animation.attachments.iter().flat_map(|a| a.values())
+                                  .flat_map(|s| s.keys()).map(|s| &s[..]));
+            slots.sort();
+            slots.dedup();
+
+            summary.push_str(&format!("animation `{}`: {}s, bones: [{}], slots: [{}]\n", name,
+                                      self.get_animation_duration(name).unwrap_or(0.0),
+                                      bones.join(", "), slots.join(", ")));
         }
+
+        summary
     }
 
-    /// Calculates the list of sprites that must be displayed and their matrix.
+    /// Writes the document as JSON.
     ///
-    /// If `elapsed` is longer than the duration of the animation, it will be modulo'd.
-    // TODO: implement draw order timeline
-    // TODO: implement events
-    // TODO: implement other attachment types
-    pub fn calculate(&self, skin: &str, animation: Option<&str>, mut elapsed: f32) 
-        -> Result<Calculation, CalculationError>
-    {
-        // adapting elapsed
-        if let Some(animation) = animation {
-            if let Some(duration) = self.get_animation_duration(animation) {
-                elapsed = elapsed % duration;
+    /// The output can be loaded back with `SpineDocument::new`. Only the elements that are
+    ///  recognized by this library are written. If the document was loaded with `new_scaled`,
+    ///  the scaled values are written.
+    pub fn to_json_writer<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        use rustc_serialize::json::ToJson;
+
+        // the curves of the `rgb` and `alpha` timelines are converted back to the format of
+        // Spine 4, so that they aren't converted a second time when the output is loaded
+        let mut source = self.source.clone();
+        if let Some(ref mut animations) = source.animations {
+            for (name, animation) in animations.iter_mut() {
+                try!(convert_channel_curves(name, animation, false)
+                    .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("{:?}", e))));
             }
         }
-        let elapsed = elapsed;
 
-        // getting a reference to the `format::Skin`
-        let skin = try!(self.source.skins.as_ref().and_then(|l| l.get(skin))
-            .ok_or(CalculationError::SkinNotFound));
+        write!(writer, "{}", source.to_json())
+    }
 
-        // getting a reference to "default" skin
-        let default_skin = try!(self.source.skins.as_ref().and_then(|l| l.get("default"))
-            .ok_or(CalculationError::SkinNotFound));
+    /// Writes the document in a binary format that `read_cache` loads much faster than JSON.
+    ///
+    /// The cache starts with a version number, and `read_cache` rejects the caches written by
+    ///  other versions of this library. Only available with the `cache` feature.
+    #[cfg(feature = "cache")]
+    pub fn write_cache<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        use bincode::SizeLimit;
+        use bincode::rustc_serialize::encode;
 
-        // getting a reference to the `format::Animation`
-        let animation: Option<&format::Animation> = match animation {
-            Some(animation) => Some(try!(self.source.animations.as_ref()
-                .and_then(|l| l.get(animation)).ok_or(CalculationError::AnimationNotFound))),
-            None => None
-        };
+        let data = try!(encode(&(self.scale, &self.source), SizeLimit::Infinite)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("{:?}", e))));
 
-        // calculating the default pose of all bones
-        let mut bones: Vec<(&format::Bone, BoneData)> = self.source.bones.as_ref().map(|bones| {
-            bones.iter().map(|bone| (bone, get_bone_default_local_setup(bone))).collect()
-        }).unwrap_or_else(|| Vec::new());
+        try!(writer.write_all(CACHE_HEADER));
+        writer.write_all(&data)
+    }
 
-        // if we are animating, adding to the default pose the calculations from the animation
-        if let Some(animation) = animation {
-            if let Some(anim_bones) = animation.bones.as_ref() {
-                for (bone_name, timelines) in anim_bones.iter() {
-                    // calculating the variation from the animation
-                    let anim_data = try!(timelines_to_bonedata(timelines, elapsed));
+    /// Loads a document written by `write_cache`.
+    ///
+    /// Returns `InvalidCache` if the cache was written by another version of this library or
+    ///  is corrupted, in which case you should load the JSON document again. Only available
+    ///  with the `cache` feature.
+    #[cfg(feature = "cache")]
+    pub fn read_cache<R: Read>(mut reader: R) -> Result<SpineDocument, LoadError> {
+        use bincode::rustc_serialize::decode;
 
-                    // adding this to the `bones` vec above
-                    match bones.iter_mut().find(|&&mut (b, _)| b.name == *bone_name) {
-                        Some(&mut (_, ref mut data)) => { *data = data.clone() + anim_data; },
-                        None => ()
-                    };
-                }
-            }
-        };
+        let mut data = Vec::new();
+        try!(reader.read_to_end(&mut data)
+            .map_err(|e| LoadError::Io(e.kind(), format!("{}", e))));
 
-        // now we have our list of bones with their relative positions
-        // adding the position of the parent to each bone
-        let bones: Vec<(&str, Matrix4<f32>)> = bones.iter().map(|&(ref bone, ref relative_data)| {
-            let mut current_matrix = relative_data.to_matrix();
-            let mut current_parent = bone.parent.as_ref();
+        if !data.starts_with(CACHE_HEADER) {
+            return Err(LoadError::InvalidCache);
+        }
 
-            loop {
-                if let Some(parent_name) = current_parent {
-                    assert!(parent_name != &bone.name);     // prevent infinite loop
+        let (scale, document): (f64, format::Document) =
+            try!(decode(&data[CACHE_HEADER.len() ..]).map_err(|_| LoadError::InvalidCache));
+        if scale == 0.0 || !scale.is_finite() {
+            return Err(LoadError::InvalidCache);
+        }
+        let sequences = get_sequences_regions(&document);
 
-                    match bones.iter().find(|&&(b, _)| b.name == *parent_name) {
-                        Some(ref p) => {
-                            current_parent = p.0.parent.as_ref();
-                            current_matrix = p.1.to_matrix() * current_matrix;
-                        },
-                        None => {
-                            current_parent = None;  // TODO: return BoneNotFound(parent_name);
-                        }
-                    }
+        Ok(SpineDocument {
+            source: document,
+            sequences: sequences,
+            scale: scale,
+            empty_animation: empty_animation(),
+        })
+    }
 
-                } else {
-                    break
-                }
-            }
+    /// Returns a fingerprint of the content of the document.
+    ///
+    /// Two documents with the same bones, slots, skins, events and animations have the same
+    ///  fingerprint, whatever the order of the elements in their JSON objects and whatever the
+    ///  elements that are not recognized by this library. Contrary to the `hash` of the
+    ///  skeleton, it is computed from the data that was actually loaded. This is useful as a
+    ///  cache key, for example to skip processing a document that didn't change.
+    pub fn get_content_hash(&self) -> u64 {
+        use rustc_serialize::json::ToJson;
+        use std::hash::{Hash, Hasher, SipHasher};
 
-            (&bone.name[..], current_matrix.clone())
+        // the objects of `Json` are sorted by key, which gives a canonical representation
+        let mut hasher = SipHasher::new();
+        self.source.to_json().to_string().hash(&mut hasher);
+        hasher.finish()
+    }
 
-        }).collect();
+    /// Returns the path of the folder that contains the images, as written in the document.
+    ///
+    /// This path is usually relative to the location of the document.
+    pub fn get_images_path(&self) -> Option<&str> {
+        self.source.skeleton.as_ref().and_then(|s| s.images.as_ref()).map(|i| &i[..])
+    }
 
-        // now taking each slot in the document and matching its bone
-        // `slots` contains the slot name, bone data, color, and attachment
-        let mut slots: Vec<(&str, Matrix4<f32>, Option<&str>, Option<&str>)> =
-            if let Some(slots) = self.source.slots.as_ref() {
-                let mut result = Vec::new();
+    /// Returns the frame rate at which the document was authored, as written in the document.
+    ///
+    /// Returns `None` if the document doesn't specify it, in which case Spine uses 30 frames per
+    ///  second.
+    pub fn get_fps(&self) -> Option<f32> {
+        self.source.skeleton.as_ref().and_then(|s| s.fps).map(|f| f as f32)
+    }
 
-                for slot in slots.iter() {
-                    let bone = try!(bones.iter().find(|&&(name, _)| name == slot.bone)
-                        .ok_or(CalculationError::BoneNotFound(&slot.bone)));
-                    result.push((&slot.name[..], bone.1, slot.color.as_ref()
-                        .map(|s| &s[..]), slot.attachment.as_ref().map(|s| &s[..])))
+    /// Returns the path of the image file of a sprite.
+    ///
+    /// This is useful if you use individual images instead of a texture atlas. The path is built
+    ///  from the images folder of the document and the name of the sprite, as returned by
+    ///  `get_possible_sprites` or `calculate`.
+    pub fn get_image_path(&self, sprite: &str) -> PathBuf {
+        let file = format!("{}.png", sprite);
+
+        match self.get_images_path() {
+            Some(images) => Path::new(images).join(file),
+            None => PathBuf::from(file),
+        }
+    }
+
+    /// Returns the list of all animations in this document.
+    pub fn get_animations_list(&self) -> Vec<&str> {
+        if let Some(ref list) = self.source.animations {
+            list.keys().map(|e| &e[..]).collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Returns the list of all skins in this document.
+    pub fn get_skins_list(&self) -> Vec<&str> {
+        if let Some(ref list) = self.source.skins {
+            list.keys().map(|e| &e[..]).collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Returns the list of all slots in this document, in the order in which they are declared.
+    ///
+    /// This is also the order in which they are drawn when no draw order timeline applies.
+    pub fn get_slots_list(&self) -> Vec<&str> {
+        if let Some(ref list) = self.source.slots {
+            list.iter().map(|s| &s.name[..]).collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Returns the name of the bone that a slot is attached to.
+    ///
+    /// Returns `None` if the slot doesn't exist.
+    pub fn get_slot_bone(&self, slot: &str) -> Option<&str> {
+        self.source.slots.as_ref()
+            .and_then(|list| list.iter().find(|s| s.name == slot))
+            .map(|s| &s.bone[..])
+    }
+
+    /// Returns the name of each slot with the name of the bone it is attached to, in the order
+    ///  in which the slots are declared.
+    ///
+    /// This lets you group the sprites by bone, since each sprite is drawn by a slot.
+    pub fn get_slots_bones(&self) -> Vec<(&str, &str)> {
+        if let Some(ref list) = self.source.slots {
+            list.iter().map(|s| (&s.name[..], &s.bone[..])).collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Returns the width and height of an attachment, as they were authored in Spine.
+    ///
+    /// The attachment is searched in `skin`, then in the default skin. The size is not
+    ///  multiplied by the scale given to `new_scaled`. Returns `None` if the attachment doesn't
+    ///  exist or doesn't have a size.
+    pub fn get_attachment_size(&self, skin: &str, slot: &str, attachment: &str)
+        -> Option<(f32, f32)>
+    {
+        let skins = match self.source.skins {
+            Some(ref s) => s,
+            None => return None
+        };
+
+        let attachment = match skins.get(skin).and_then(|s| s.get(slot))
+                                   .and_then(|s| s.get(attachment))
+                                   .or_else(|| skins.get("default").and_then(|s| s.get(slot))
+                                                    .and_then(|s| s.get(attachment)))
+        {
+            Some(a) => a,
+            None => return None
+        };
+
+        match (attachment.width, attachment.height) {
+            (Some(width), Some(height)) => Some(((width / self.scale) as f32,
+                                                 (height / self.scale) as f32)),
+            _ => None
+        }
+    }
+
+    /// Returns the largest number of vertices needed to draw one of the attachments of this
+    ///  document.
+    ///
+    /// Regions need 4 vertices and meshes one per texture coordinate. The attachments that are
+    ///  not drawn, like bounding boxes and paths, are ignored. This lets a renderer allocate a
+    ///  vertex buffer once and reuse it for every attachment.
+    pub fn get_max_attachment_vertices(&self) -> usize {
+        let mut result = 0;
+
+        for skin in self.source.skins.iter().flat_map(|s| s.values()) {
+            for attachment in skin.values().flat_map(|a| a.values()) {
+                let count = match attachment.type_ {
+                    None | Some(AttachmentType::Region) |
+                    Some(AttachmentType::RegionSequence) => 4,
+                    Some(AttachmentType::Mesh) | Some(AttachmentType::LinkedMesh) => {
+                        attachment.uvs.as_ref().map(|uvs| uvs.len() / 2).unwrap_or(0)
+                    },
+                    _ => 0
+                };
+
+                result = cmp::max(result, count);
+            }
+        }
+
+        result
+    }
+
+    /// Returns the names of all the attachments that can be shown by this document, in
+    ///  alphabetical order.
+    ///
+    /// This includes the setup attachments of the slots, the attachments of all skins and the
+    ///  attachments set by the animations. Contrary to `get_possible_sprites`, these are the
+    ///  names of the attachments and not of the regions they display.
+    pub fn get_attachments_list(&self) -> Vec<&str> {
+        let mut result = Vec::new();
+
+        if let Some(ref slots) = self.source.slots {
+            result.extend(slots.iter().filter_map(|s| s.attachment.as_ref()).map(|a| &a[..]));
+        }
+
+        if let Some(ref skins) = self.source.skins {
+            for skin in skins.values() {
+                for attachments in skin.values() {
+                    result.extend(attachments.keys().map(|a| &a[..]));
+                }
+            }
+        }
+
+        if let Some(ref animations) = self.source.animations {
+            for animation in animations.values() {
+                if let Some(ref slots) = animation.slots {
+                    for timelines in slots.values() {
+                        if let Some(ref timeline) = timelines.attachment {
+                            result.extend(timeline.iter().filter_map(|k| k.name.as_ref())
+                                                  .map(|a| &a[..]));
+                        }
+                    }
+                }
+            }
+        }
+
+        result.sort();
+        result.dedup();
+        result
+    }
+
+    /// Returns the list of all bones in this document.
+    ///
+    /// A parent is always before its children.
+    pub fn get_bones_list(&self) -> Vec<&str> {
+        if let Some(ref list) = self.source.bones {
+            list.iter().map(|b| &b.name[..]).collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Returns the name of the parent of a bone.
+    ///
+    /// Returns `None` if the bone doesn't exist or is a root bone.
+    pub fn get_bone_parent(&self, bone: &str) -> Option<&str> {
+        self.source.bones.as_ref()
+            .and_then(|list| list.iter().find(|b| b.name == bone))
+            .and_then(|b| b.parent.as_ref()).map(|p| &p[..])
+    }
+
+    /// Returns whether a bone inherits the scale and the rotation of its parent, in this order.
+    ///
+    /// Both default to true. Returns `None` if the bone doesn't exist.
+    pub fn get_bone_inherit(&self, bone: &str) -> Option<(bool, bool)> {
+        self.source.bones.as_ref()
+            .and_then(|list| list.iter().find(|b| b.name == bone))
+            .map(|b| (b.inheritScale.unwrap_or(true), b.inheritRotation.unwrap_or(true)))
+    }
+
+    /// Returns the names of the direct children of a bone.
+    pub fn get_bone_children(&self, bone: &str) -> Vec<&str> {
+        if let Some(ref list) = self.source.bones {
+            list.iter().filter(|b| b.parent.as_ref().map(|p| p == bone).unwrap_or(false))
+                .map(|b| &b.name[..]).collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Returns the names of the children of a bone, of their children, and so on.
+    ///
+    /// A parent is always before its children.
+    pub fn get_bone_descendants(&self, bone: &str) -> Vec<&str> {
+        let mut result: Vec<&str> = Vec::new();
+
+        if let Some(ref list) = self.source.bones {
+            // the bones are sorted so that parents come before their children
+            for b in list.iter() {
+                if let Some(ref parent) = b.parent {
+                    if parent == bone || result.iter().any(|r| r == parent) {
+                        result.push(&b.name[..]);
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Returns true if an animation is in the document.
+    pub fn has_animation(&self, name: &str) -> bool {
+        if let Some(ref list) = self.source.animations {
+            list.get(&name.to_string()).is_some()
+        } else {
+            false
+        }
+    }
+
+    /// Returns the number of animations in the document.
+    pub fn get_animations_count(&self) -> usize {
+        self.source.animations.as_ref().map(|list| list.len()).unwrap_or(0)
+    }
+
+    /// Returns true if a skin is in the document.
+    pub fn has_skin(&self, name: &str) -> bool {
+        if let Some(ref list) = self.source.skins {
+            list.get(&name.to_string()).is_some()
+        } else {
+            false
+        }
+    }
+
+    /// Returns the list of all events in this document.
+    pub fn get_events_list(&self) -> Vec<&str> {
+        if let Some(ref list) = self.source.events {
+            list.keys().map(|e| &e[..]).collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Returns the default values of an event, as defined in the document.
+    ///
+    /// Returns `None` if the event doesn't exist.
+    pub fn get_event(&self, name: &str) -> Option<Event> {
+        let list = match self.source.events {
+            Some(ref list) => list,
+            None => return None
+        };
+
+        list.iter().find(|&(n, _)| n == name).map(|(name, event)| {
+            Event {
+                name: &name[..],
+                int: event.int_.unwrap_or(0),
+                float: event.float_.unwrap_or(0.0) as f32,
+                string: event.string.as_ref().map(|s| &s[..]),
+                audio: event.audio.as_ref().map(|s| &s[..]),
+                volume: event.volume.unwrap_or(1.0) as f32,
+                balance: event.balance.unwrap_or(0.0) as f32,
+            }
+        })
+    }
+
+    /// Returns an estimate of the number of bytes used by this document in memory.
+    ///
+    /// This is the sum of the sizes of all the elements of the document and of the containers
+    ///  that hold them. It is only an estimate, as the overhead of the allocator and the exact
+    ///  layout of the containers are not taken into account.
+    pub fn get_memory_usage(&self) -> usize {
+        use format::HeapSize;
+
+        mem::size_of::<SpineDocument>() + self.source.heap_size() + self.sequences.heap_size()
+    }
+
+    /// Returns the events fired by an animation between two times, sorted by time.
+    ///
+    /// The interval is half-open: events at `from` are not returned but events at `to` are. This
+    ///  means that you can pass the time of the previous frame as `from` and the time of the
+    ///  current frame as `to` without firing an event twice. Pass a negative `from` in order to
+    ///  fire the events at time 0 on the first frame.
+    ///
+    /// The times are not modulo'd: the animation is looped, and the events are fired once per
+    ///  loop.
+    ///
+    /// Returns `None` if the animation doesn't exist, if `from` or `to` isn't finite, or if the
+    ///  interval contains more than `MAX_FRAMES` loops of the animation.
+    pub fn get_fired_events(&self, animation: &str, from: f32, to: f32) -> Option<Vec<Event>> {
+        let duration = match self.get_animation_duration(animation) {
+            Some(d) => d,
+            None => return None
+        };
+
+        if !from.is_finite() || !to.is_finite() {
+            return None;
+        }
+
+        let keyframes = match self.get_animation(animation).and_then(|a| a.events.as_ref()) {
+            Some(k) => k,
+            None => return Some(Vec::new())
+        };
+
+        let mut fired: Vec<(f32, &format::EventKeyframe)> = Vec::new();
+
+        for keyframe in keyframes.iter() {
+            let time = keyframe.time as f32;
+
+            if duration <= 0.0 {
+                if from < time && time <= to { fired.push((time, keyframe)); }
+                continue;
+            }
+
+            // indices of the first and last loops where the event is fired between `from` and
+            // `to`, widened by one loop because of rounding errors
+            let first = cmp::max(((from - time) as f64 / duration as f64).floor() as i64, 0);
+            let last = ((to - time) as f64 / duration as f64).floor() as i64 + 1;
+            if last - first > MAX_FRAMES as i64 {
+                return None;
+            }
+
+            for loop_index in first .. last + 1 {
+                let loop_time = time + loop_index as f32 * duration;
+                if from < loop_time && loop_time <= to { fired.push((loop_time, keyframe)); }
+            }
+        }
+
+        fired.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        Some(fired.into_iter().map(|(_, keyframe)| self.get_keyframe_event(keyframe)).collect())
+    }
+
+    /// Builds the `Event` fired by a keyframe, using the values of the event's definition for
+    ///  the values that the keyframe doesn't have.
+    fn get_keyframe_event<'a>(&'a self, keyframe: &'a format::EventKeyframe) -> Event<'a> {
+        let definition = self.source.events.as_ref().and_then(|e| e.get(&keyframe.name));
+
+        Event {
+            name: &keyframe.name[..],
+            int: keyframe.int_.or(definition.and_then(|d| d.int_)).unwrap_or(0),
+            float: keyframe.float_.or(definition.and_then(|d| d.float_)).unwrap_or(0.0) as f32,
+            string: keyframe.string_.as_ref()
+                            .or(definition.and_then(|d| d.string.as_ref())).map(|s| &s[..]),
+            audio: definition.and_then(|d| d.audio.as_ref()).map(|s| &s[..]),
+            volume: keyframe.volume.or(definition.and_then(|d| d.volume)).unwrap_or(1.0) as f32,
+            balance: keyframe.balance.or(definition.and_then(|d| d.balance))
+                             .unwrap_or(0.0) as f32,
+        }
+    }
+
+    /// Returns the duration of an animation.
+    ///
+    /// Returns `None` if the animation doesn't exist.
+    pub fn get_animation_duration(&self, animation: &str) -> Option<f32> {
+        // getting a reference to the `format::Animation`
+        let animation = match self.get_animation(animation) {
+            Some(a) => a,
+            None => return None
+        };
+
+        // this contains the final result
+        let mut result = 0.0f64;
+
+        // checking the bones
+        if let Some(ref bones) = animation.bones {
+            for timelines in bones.values() {
+                if let Some(ref translate) = timelines.translate.as_ref() {
+                    for elem in translate.iter() {
+                        if elem.time > result { result = elem.time }
+                    }
+                }
+                if let Some(ref rotate) = timelines.rotate.as_ref() {
+                    for elem in rotate.iter() {
+                        if elem.time > result { result = elem.time }
+                    }
+                }
+                if let Some(ref scale) = timelines.scale.as_ref() {
+                    for elem in scale.iter() {
+                        if elem.time > result { result = elem.time }
+                    }
+                }
+            }
+        }
+
+        // checking the slots
+        if let Some(ref slots) = animation.slots {
+            for timelines in slots.values() {
+                if let Some(ref attachment) = timelines.attachment.as_ref() {
+                    for elem in attachment.iter() {
+                        if elem.time > result { result = elem.time }
+                    }
+                }
+                if let Some(ref color) = timelines.color.as_ref() {
+                    for elem in color.iter() {
+                        let time = elem.time.unwrap_or(0.0);
+                        if time > result { result = time }
+                    }
+                }
+                if let Some(ref rgb) = timelines.rgb.as_ref() {
+                    for elem in rgb.iter() {
+                        let time = elem.time.unwrap_or(0.0);
+                        if time > result { result = time }
+                    }
+                }
+                if let Some(ref alpha) = timelines.alpha.as_ref() {
+                    for elem in alpha.iter() {
+                        let time = elem.time.unwrap_or(0.0);
+                        if time > result { result = time }
+                    }
+                }
+            }
+        }
+
+        // checking the sequences of the attachments
+        for timelines in get_attachments_timelines(animation) {
+            if let Some(ref sequence) = timelines.sequence.as_ref() {
+                for elem in sequence.iter() {
+                    if elem.time > result { result = elem.time }
+                }
+            }
+        }
+
+        // checking the events
+        if let Some(ref events) = animation.events {
+            for elem in events.iter() {
+                if elem.time > result { result = elem.time }
+            }
+        }
+
+        // checking the draw order
+        if let Some(ref draworder) = animation.draworder {
+            for elem in draworder.iter() {
+                if elem.time > result { result = elem.time }
+            }
+        }
+
+        // returning
+        Some(result as f32)
+    }
+
+    /// Returns the time of the earliest keyframe or event of an animation.
+    ///
+    /// This is usually 0, but some animations don't start at the beginning of their timeline.
+    ///  Together with `get_animation_duration`, this gives the range during which the animation
+    ///  actually changes something. Returns 0 if the animation has no keyframe.
+    ///
+    /// Returns `None` if the animation doesn't exist.
+    pub fn get_animation_start_time(&self, animation: &str) -> Option<f32> {
+        let keyframes = match self.get_animation_keyframe_times(animation) {
+            Some(k) => k,
+            None => return None
+        };
+        let events = self.get_animation_event_times(animation).unwrap_or_else(|| Vec::new());
+
+        let start = match (keyframes.first(), events.first()) {
+            (Some(&k), Some(&e)) => k.min(e),
+            (Some(&k), None) => k,
+            (None, Some(&e)) => e,
+            (None, None) => 0.0
+        };
+
+        Some(start)
+    }
+
+    /// Returns a handle to an animation, to calculate it many times without measuring its
+    ///  duration each time, which requires going through all its keyframes.
+    ///
+    /// Returns `None` if the animation doesn't exist.
+    pub fn get_animation_handle(&self, animation: &str) -> Option<AnimationHandle> {
+        let name = match self.source.animations.as_ref()
+                                               .and_then(|a| a.keys().find(|n| *n == animation))
+        {
+            Some(name) => &name[..],
+            None if animation == EMPTY_ANIMATION => EMPTY_ANIMATION,
+            None => return None
+        };
+
+        Some(AnimationHandle {
+            document: self,
+            name: name,
+            duration: self.get_animation_duration(name).unwrap_or(0.0),
+        })
+    }
+
+    /// Returns the draw orders of an animation with the time from which each of them applies,
+    ///  sorted by time.
+    ///
+    /// Each draw order contains the indices of the slots, in the order of `get_slots_list`,
+    ///  from bottom to top. The first element is always at time 0, so the draw order at a
+    ///  given time is the one of the last element whose time is lower or equal. Calculating
+    ///  this list once lets renderers that keep a persistent draw list look up the draw order
+    ///  instead of applying the offsets of the timeline at each frame.
+    pub fn get_animation_draw_orders(&self, animation: &str)
+        -> Result<Vec<(f32, Vec<usize>)>, CalculationError>
+    {
+        let animation = try!(self.get_animation(animation)
+                                 .ok_or(CalculationError::AnimationNotFound));
+        let slots = self.source.slots.as_ref().map(|s| &s[..]).unwrap_or(&[]);
+
+        let mut result = Vec::new();
+        if let Some(ref draworder) = animation.draworder {
+            for keyframe in draworder.iter() {
+                let time = keyframe.time as f32;
+                result.push((time, try!(get_draw_order(slots, Some(draworder), time))));
+            }
+        }
+
+        if result.first().map(|&(time, _)| time > 0.0).unwrap_or(true) {
+            result.insert(0, (0.0, (0 .. slots.len()).collect()));
+        }
+
+        Ok(result)
+    }
+
+    /// Returns the sorted list of times at which an animation has a keyframe.
+    ///
+    /// This includes the keyframes of the bones, slots and draw order timelines, but not the
+    ///  events. Use `get_animation_event_times` for these.
+    ///
+    /// Returns `None` if the animation doesn't exist.
+    pub fn get_animation_keyframe_times(&self, animation: &str) -> Option<Vec<f32>> {
+        let animation = match self.get_animation(animation) {
+            Some(a) => a,
+            None => return None
+        };
+
+        let mut result = Vec::new();
+
+        if let Some(ref bones) = animation.bones {
+            for timelines in bones.values() {
+                if let Some(ref translate) = timelines.translate {
+                    result.extend(translate.iter().map(|e| e.time as f32));
+                }
+                if let Some(ref rotate) = timelines.rotate {
+                    result.extend(rotate.iter().map(|e| e.time as f32));
+                }
+                if let Some(ref scale) = timelines.scale {
+                    result.extend(scale.iter().map(|e| e.time as f32));
+                }
+            }
+        }
+
+        if let Some(ref slots) = animation.slots {
+            for timelines in slots.values() {
+                if let Some(ref attachment) = timelines.attachment {
+                    result.extend(attachment.iter().map(|e| e.time as f32));
+                }
+                if let Some(ref color) = timelines.color {
+                    result.extend(color.iter().map(|e| e.time.unwrap_or(0.0) as f32));
+                }
+                if let Some(ref rgb) = timelines.rgb {
+                    result.extend(rgb.iter().map(|e| e.time.unwrap_or(0.0) as f32));
+                }
+                if let Some(ref alpha) = timelines.alpha {
+                    result.extend(alpha.iter().map(|e| e.time.unwrap_or(0.0) as f32));
+                }
+            }
+        }
+
+        for timelines in get_attachments_timelines(animation) {
+            if let Some(ref sequence) = timelines.sequence {
+                result.extend(sequence.iter().map(|e| e.time as f32));
+            }
+        }
+
+        if let Some(ref draworder) = animation.draworder {
+            result.extend(draworder.iter().map(|e| e.time as f32));
+        }
+
+        result.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        result.dedup();
+        Some(result)
+    }
+
+    /// Returns the sorted list of times at which an animation fires an event.
+    ///
+    /// Returns `None` if the animation doesn't exist.
+    pub fn get_animation_event_times(&self, animation: &str) -> Option<Vec<f32>> {
+        let animation = match self.get_animation(animation) {
+            Some(a) => a,
+            None => return None
+        };
+
+        let mut result: Vec<f32> = animation.events.as_ref()
+            .map(|events| events.iter().map(|e| e.time as f32).collect())
+            .unwrap_or_else(|| Vec::new());
+
+        result.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        result.dedup();
+        Some(result)
+    }
+
+    /// Returns all the events fired by an animation with the time at which they are fired,
+    ///  sorted by time.
+    ///
+    /// Events that are fired at the same time are in the order of the document.
+    ///
+    /// Returns `None` if the animation doesn't exist.
+    pub fn get_animation_events(&self, animation: &str) -> Option<Vec<(f32, Event)>> {
+        let animation = match self.get_animation(animation) {
+            Some(a) => a,
+            None => return None
+        };
+
+        let mut result: Vec<(f32, Event)> = animation.events.as_ref()
+            .map(|events| events.iter().map(|e| (e.time as f32, self.get_keyframe_event(e)))
+                                .collect())
+            .unwrap_or_else(|| Vec::new());
+
+        // the sort is stable
+        result.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        Some(result)
+    }
+
+    /// Returns the name of the attachment shown by a slot at the given time of an animation,
+    ///  without calculating the sprites.
+    ///
+    /// This is cheaper than `calculate` when you only need to know what a slot displays, for
+    ///  example to know if a shield is raised. If `elapsed` is longer than the duration of the
+    ///  animation, it will be modulo'd.
+    ///
+    /// Returns `None` if the slot is hidden or doesn't exist.
+    pub fn get_slot_attachment(&self, animation: Option<&str>, slot: &str, elapsed: f32)
+        -> Result<Option<&str>, CalculationError>
+    {
+        let setup = match self.source.slots.as_ref()
+                                           .and_then(|s| s.iter().find(|s| s.name == slot))
+        {
+            Some(slot) => slot.attachment.as_ref().map(|a| &a[..]),
+            None => return Ok(None)
+        };
+
+        let name = match animation {
+            Some(name) => name,
+            None => return Ok(setup)
+        };
+
+        let timeline = try!(self.get_animation(name).ok_or(CalculationError::AnimationNotFound))
+                           .slots.as_ref().and_then(|s| s.get(slot))
+                           .and_then(|t| t.attachment.as_ref());
+
+        match timeline {
+            Some(timeline) => {
+                let elapsed = try!(self.loop_elapsed(Some(name), elapsed, &Default::default()));
+                Ok(attachment_at(timeline, elapsed).unwrap_or(setup))
+            },
+            None => Ok(setup)
+        }
+    }
+
+    /// Samples the timelines of a single bone of an animation, for example to write a custom
+    ///  sampler or to drive something else than the skeleton with them.
+    ///
+    /// Returns the translation and the rotation that the animation adds to the setup pose of
+    ///  the bone, and the scale that multiplies it. The components that are not animated are
+    ///  0, 0 and 1 respectively. `elapsed` is not modulo'd, and past the last keyframe its
+    ///  value is kept.
+    ///
+    /// Returns `Ok(None)` if the animation doesn't animate this bone.
+    pub fn sample_bone_timeline(&self, animation: &str, bone: &str, elapsed: f32)
+        -> Result<Option<((f32, f32), f32, (f32, f32))>, CalculationError>
+    {
+        let animation = try!(self.get_animation(animation)
+                                 .ok_or(CalculationError::AnimationNotFound));
+
+        match animation.bones.as_ref().and_then(|b| b.get(bone)) {
+            Some(timelines) => {
+                let data = try!(timelines_to_bonedata(timelines, elapsed, &Default::default()));
+                Ok(Some((data.position, data.rotation, data.scale)))
+            },
+            None => Ok(None)
+        }
+    }
+
+    /// Samples the timelines of a single slot of an animation.
+    ///
+    /// Returns the color of the slot, the channels that are not animated being taken from the
+    ///  setup pose, and its attachment, `Some(None)` meaning that the slot is hidden. Each of
+    ///  them is `None` if the animation doesn't change it. `elapsed` is not modulo'd.
+    ///
+    /// Returns `Ok(None)` if the animation doesn't animate this slot.
+    pub fn sample_slot_timeline<'a>(&'a self, animation: &str, slot: &'a str, elapsed: f32)
+        -> Result<Option<(Option<Rgba<u8>>, Option<Option<&'a str>>)>, CalculationError<'a>>
+    {
+        let animation = try!(self.get_animation(animation)
+                                 .ok_or(CalculationError::AnimationNotFound));
+        let setup = try!(self.source.slots.as_ref()
+                                          .and_then(|s| s.iter().find(|s| s.name == slot))
+                                          .ok_or(CalculationError::SlotNotFound(slot)));
+        let setup = try!(parse_optional_color(&setup.color));
+
+        match animation.slots.as_ref().and_then(|s| s.get(slot)) {
+            Some(timelines) => {
+                Ok(Some(try!(timelines_to_slotdata(timelines, elapsed, setup,
+                                                   &Default::default()))))
+            },
+            None => Ok(None)
+        }
+    }
+
+    /// Returns true if the pose calculated at time `to` of an animation may be different from
+    ///  the one calculated at time `from`.
+    ///
+    /// This is the case if a keyframe is crossed between the two times, or if a value is being
+    ///  interpolated between two keyframes. Skipping the calculation when this returns false
+    ///  saves work during the static parts of an animation. The times are not modulo'd by the
+    ///  duration of the animation. If `to` is before `from`, the animation is assumed to have
+    ///  looped and this returns true.
+    ///
+    /// Returns false if the animation doesn't exist.
+    pub fn would_change(&self, animation: &str, from: f32, to: f32) -> bool {
+        let animation = match self.get_animation(animation) {
+            Some(a) => a,
+            None => return false
+        };
+
+        if to == from {
+            return false;
+        } else if to < from {
+            return true;
+        }
+
+        // each keyframe is given with whether the value changes until the next keyframe, or
+        // after it if it is the last one
+        let changes = |keyframes: &[(f64, bool)]| {
+            keyframes.iter().any(|&(time, _)| from < time as f32 && time as f32 <= to) ||
+            keyframes.iter().enumerate().any(|(index, &(time, changing))| {
+                changing && from >= time as f32 &&
+                keyframes.get(index + 1).map(|&(next, _)| from < next as f32).unwrap_or(true)
+            })
+        };
+
+        let curves = |keyframes: Vec<(f64, &Option<format::TimelineCurve>)>| {
+            let count = keyframes.len();
+            keyframes.into_iter().enumerate().map(|(index, (time, curve))| {
+                let stepped = match curve {
+                    &Some(format::TimelineCurve::CurvePredefined(ref c)) => c == "stepped",
+                    _ => false
+                };
+                (time, index + 1 < count && !stepped)
+            }).collect::<Vec<_>>()
+        };
+
+        if let Some(ref bones) = animation.bones {
+            for timelines in bones.values() {
+                if let Some(ref translate) = timelines.translate {
+                    if changes(&curves(translate.iter().map(|k| (k.time, &k.curve)).collect())) {
+                        return true;
+                    }
+                }
+                if let Some(ref rotate) = timelines.rotate {
+                    if changes(&curves(rotate.iter().map(|k| (k.time, &k.curve)).collect())) {
+                        return true;
+                    }
+                }
+                if let Some(ref scale) = timelines.scale {
+                    if changes(&curves(scale.iter().map(|k| (k.time, &k.curve)).collect())) {
+                        return true;
+                    }
+                }
+            }
+        }
+
+        if let Some(ref slots) = animation.slots {
+            for timelines in slots.values() {
+                if let Some(ref attachment) = timelines.attachment {
+                    let keyframes: Vec<_> = attachment.iter().map(|k| (k.time, false)).collect();
+                    if changes(&keyframes) {
+                        return true;
+                    }
+                }
+                if let Some(ref color) = timelines.color {
+                    let keyframes = color.iter().map(|k| (k.time.unwrap_or(0.0), &k.curve));
+                    if changes(&curves(keyframes.collect())) {
+                        return true;
+                    }
+                }
+                if let Some(ref rgb) = timelines.rgb {
+                    let keyframes = rgb.iter().map(|k| (k.time.unwrap_or(0.0), &k.curve));
+                    if changes(&curves(keyframes.collect())) {
+                        return true;
+                    }
+                }
+                if let Some(ref alpha) = timelines.alpha {
+                    let keyframes = alpha.iter().map(|k| (k.time.unwrap_or(0.0), &k.curve));
+                    if changes(&curves(keyframes.collect())) {
+                        return true;
+                    }
+                }
+            }
+        }
+
+        // the sequences that are not held change regions over time
+        for timelines in get_attachments_timelines(animation) {
+            if let Some(ref sequence) = timelines.sequence {
+                let keyframes: Vec<_> = sequence.iter().map(|k| {
+                    let mode = k.mode.unwrap_or(format::SequenceMode::Hold);
+                    (k.time, mode != format::SequenceMode::Hold && k.delay.unwrap_or(0.0) > 0.0)
+                }).collect();
+                if changes(&keyframes) {
+                    return true;
+                }
+            }
+        }
+
+        if let Some(ref draworder) = animation.draworder {
+            let keyframes: Vec<_> = draworder.iter().map(|k| (k.time, false)).collect();
+            if changes(&keyframes) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Returns true if a slot has attachment or color keyframes around the given time of an
+    ///  animation.
+    ///
+    /// Returns false if the animation or the slot doesn't exist.
+    pub fn is_slot_animated(&self, animation: &str, slot: &str, elapsed: f32) -> bool {
+        let timelines = match self.get_animation(animation).and_then(|a| a.slots.as_ref())
+                                  .and_then(|s| s.get(slot))
+        {
+            Some(t) => t,
+            None => return false
+        };
+
+        let covers = |first: Option<f64>, last: Option<f64>| {
+            match (first, last) {
+                (Some(first), Some(last)) => elapsed >= first as f32 && elapsed <= last as f32,
+                _ => false
+            }
+        };
+
+        timelines.attachment.as_ref().map(|t| covers(t.first().map(|k| k.time),
+                                                      t.last().map(|k| k.time)))
+                 .unwrap_or(false) ||
+        timelines.color.as_ref().map(|t| covers(t.first().map(|k| k.time.unwrap_or(0.0)),
+                                                 t.last().map(|k| k.time.unwrap_or(0.0))))
+                 .unwrap_or(false) ||
+        timelines.rgb.as_ref().map(|t| covers(t.first().map(|k| k.time.unwrap_or(0.0)),
+                                               t.last().map(|k| k.time.unwrap_or(0.0))))
+                 .unwrap_or(false) ||
+        timelines.alpha.as_ref().map(|t| covers(t.first().map(|k| k.time.unwrap_or(0.0)),
+                                                 t.last().map(|k| k.time.unwrap_or(0.0))))
+                 .unwrap_or(false)
+    }
+
+    /// Returns, for each bone and slot timeline of an animation, whether the given time is
+    ///  between two of its keyframes or outside of them.
+    ///
+    /// This is a debugging aid. A timeline whose last keyframe is before the end of the
+    ///  animation keeps its last value until the animation loops, which can look like a
+    ///  stutter. The timelines are sorted by bone or slot name, then by kind.
+    ///
+    /// If `elapsed` is longer than the duration of the animation, it will be modulo'd.
+    ///
+    /// Returns `None` if the animation doesn't exist.
+    pub fn get_timelines_sampling(&self, animation: &str, mut elapsed: f32)
+        -> Option<Vec<TimelineSampling>>
+    {
+        if let Some(duration) = self.get_animation_duration(animation) {
+            if duration > 0.0 { elapsed = elapsed % duration; }
+        }
+
+        let animation = match self.get_animation(animation) {
+            Some(a) => a,
+            None => return None
+        };
+
+        let mut result = Vec::new();
+
+        if let Some(ref bones) = animation.bones {
+            for (name, timelines) in bones.iter() {
+                let kinds = [
+                    ("translate", timelines.translate.as_ref()
+                                           .map(|t| (t.first().map(|k| k.time),
+                                                     t.last().map(|k| k.time)))),
+                    ("rotate", timelines.rotate.as_ref()
+                                        .map(|t| (t.first().map(|k| k.time),
+                                                  t.last().map(|k| k.time)))),
+                    ("scale", timelines.scale.as_ref()
+                                       .map(|t| (t.first().map(|k| k.time),
+                                                 t.last().map(|k| k.time)))),
+                ];
+
+                for &(kind, keyframes) in kinds.iter() {
+                    if let Some((Some(first), Some(last))) = keyframes {
+                        result.push(TimelineSampling {
+                            target: &name[..],
+                            timeline: kind,
+                            position: get_sampling_position(first, last, elapsed),
+                        });
+                    }
+                }
+            }
+        }
+
+        if let Some(ref slots) = animation.slots {
+            for (name, timelines) in slots.iter() {
+                let kinds = [
+                    ("attachment", timelines.attachment.as_ref()
+                                            .map(|t| (t.first().map(|k| k.time),
+                                                      t.last().map(|k| k.time)))),
+                    ("color", timelines.color.as_ref()
+                                       .map(|t| (t.first().map(|k| k.time.unwrap_or(0.0)),
+                                                 t.last().map(|k| k.time.unwrap_or(0.0))))),
+                    ("rgb", timelines.rgb.as_ref()
+                                     .map(|t| (t.first().map(|k| k.time.unwrap_or(0.0)),
+                                               t.last().map(|k| k.time.unwrap_or(0.0))))),
+                    ("alpha", timelines.alpha.as_ref()
+                                       .map(|t| (t.first().map(|k| k.time.unwrap_or(0.0)),
+                                                 t.last().map(|k| k.time.unwrap_or(0.0))))),
+                ];
+
+                for &(kind, keyframes) in kinds.iter() {
+                    if let Some((Some(first), Some(last))) = keyframes {
+                        result.push(TimelineSampling {
+                            target: &name[..],
+                            timeline: kind,
+                            position: get_sampling_position(first, last, elapsed),
+                        });
+                    }
+                }
+            }
+        }
+
+        result.sort_by(|a, b| (a.target, a.timeline).cmp(&(b.target, b.timeline)));
+        Some(result)
+    }
+
+    /// Returns a list of all possible sprites when drawing.
+    ///
+    /// The purpose of this function is to allow you to preload what you need.
+    pub fn get_possible_sprites(&self) -> Vec<&str> {
+        if let Some(ref list) = self.source.skins {
+            let mut result = Vec::new();
+
+            for (skin_name, skin) in list.iter() {
+                for (slot_name, slot) in skin.iter() {
+                    for (name, vals) in slot.iter() {
+                        match self.get_sequence_regions(skin_name, slot_name, name) {
+                            Some(regions) => result.extend(regions.iter().map(|r| &r[..])),
+                            None => result.push(get_sprite_name(name, vals)),
+                        }
+                    }
+                }
+            }
+
+            result.sort();
+            result.dedup();
+            result
+
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Calculates the world matrix of each bone in the setup pose, in the same order as with
+    ///  `calculate_bones`.
+    ///
+    /// This is the same as `calculate_bones(None, 0.0)`, but it can't fail. It is useful to
+    ///  place things relative to the bones when nothing is animated, or to measure how much an
+    ///  animation moves them.
+    pub fn calculate_setup_bones(&self) -> Vec<(&str, Matrix4<f32>)> {
+        let bones = self.source.bones.iter().flat_map(|b| b.iter())
+                        .map(|bone| (bone, get_bone_default_local_setup(bone))).collect();
+        self.bones_local_to_world(bones)
+    }
+
+    /// Calculates the world matrix of each bone.
+    ///
+    /// This is useful if you do the skinning yourself, for example on the GPU. The bones are
+    ///  returned in the same order as in the document, which means that a parent is always
+    ///  before its children.
+    ///
+    /// If `elapsed` is longer than the duration of the animation, it will be modulo'd.
+    pub fn calculate_bones(&self, animation: Option<&str>, elapsed: f32)
+        -> Result<Vec<(&str, Matrix4<f32>)>, CalculationError>
+    {
+        self.calculate_bones_with_options(animation, elapsed, &Default::default())
+    }
+
+    /// Same as `calculate_bones`, but with additional options.
+    pub fn calculate_bones_with_options(&self, animation: Option<&str>, elapsed: f32,
+        options: &CalculationOptions) -> Result<Vec<(&str, Matrix4<f32>)>, CalculationError>
+    {
+        // adapting elapsed
+        let elapsed = try!(self.loop_elapsed(animation, elapsed, options));
+
+        // getting a reference to the `format::Animation`
+        let animation: Option<&format::Animation> = match animation {
+            Some(animation) => Some(try!(self.get_animation(animation)
+                .ok_or(CalculationError::AnimationNotFound))),
+            None => None
+        };
+
+        self.calculate_bones_matrices(animation, elapsed, options)
+    }
+
+    /// Calculates the world matrix and the length of each bone, in the same order as with
+    ///  `calculate_bones`.
+    ///
+    /// This is meant to seed a physics simulation when handing a character over to a ragdoll.
+    ///  A bone goes from the origin of its matrix to `length` along its X axis. Bones without
+    ///  a length have a length of 0.
+    ///
+    /// If `elapsed` is longer than the duration of the animation, it will be modulo'd.
+    pub fn calculate_ragdoll_snapshot(&self, animation: Option<&str>, elapsed: f32)
+        -> Result<Vec<(&str, Matrix4<f32>, f32)>, CalculationError>
+    {
+        let bones = try!(self.calculate_bones(animation, elapsed));
+        let lengths = self.source.bones.iter().flat_map(|b| b.iter())
+                                       .map(|b| b.length.unwrap_or(0.0) as f32);
+
+        Ok(bones.into_iter().zip(lengths).map(|((name, matrix), length)| (name, matrix, length))
+                .collect())
+    }
+
+    /// Calculates the matrix of each bone relative to its parent.
+    ///
+    /// This is the transformation of the setup pose modified by the animation, before the
+    ///  transformations of the parents are applied. The bones are in the same order as with
+    ///  `calculate_bones`.
+    ///
+    /// If `elapsed` is longer than the duration of the animation, it will be modulo'd.
+    pub fn calculate_local_bones(&self, animation: Option<&str>, elapsed: f32)
+        -> Result<Vec<(&str, Matrix4<f32>)>, CalculationError>
+    {
+        self.calculate_local_bones_with_options(animation, elapsed, &Default::default())
+    }
+
+    /// Same as `calculate_local_bones`, but with additional options.
+    pub fn calculate_local_bones_with_options(&self, animation: Option<&str>, elapsed: f32,
+        options: &CalculationOptions) -> Result<Vec<(&str, Matrix4<f32>)>, CalculationError>
+    {
+        // adapting elapsed
+        let elapsed = try!(self.loop_elapsed(animation, elapsed, options));
+
+        // getting a reference to the `format::Animation`
+        let animation: Option<&format::Animation> = match animation {
+            Some(animation) => Some(try!(self.get_animation(animation)
+                .ok_or(CalculationError::AnimationNotFound))),
+            None => None
+        };
+
+        let mut bones = try!(self.calculate_bones_local(animation, elapsed, options));
+        apply_bone_overrides(&mut bones, options);
+        Ok(bones.iter().map(|&(bone, ref data)| (&bone.name[..], data.to_matrix())).collect())
+    }
+
+    /// Calculates the color of each slot, without calculating the bones and the attachments.
+    ///
+    /// This is much cheaper than `calculate` if you only need to tint sprites. The slots are
+    ///  returned in the same order as in the document, even if they don't show anything.
+    ///
+    /// If `elapsed` is longer than the duration of the animation, it will be modulo'd.
+    pub fn calculate_colors(&self, animation: Option<&str>, elapsed: f32)
+        -> Result<Vec<(&str, Rgba<u8>)>, CalculationError>
+    {
+        self.calculate_colors_with_options(animation, elapsed, &Default::default())
+    }
+
+    /// Same as `calculate_colors`, but with additional options.
+    pub fn calculate_colors_with_options(&self, animation: Option<&str>, elapsed: f32,
+        options: &CalculationOptions) -> Result<Vec<(&str, Rgba<u8>)>, CalculationError>
+    {
+        // adapting elapsed
+        let elapsed = try!(self.loop_elapsed(animation, elapsed, options));
+
+        // getting a reference to the `format::Animation`
+        let animation: Option<&format::Animation> = match animation {
+            Some(animation) => Some(try!(self.get_animation(animation)
+                .ok_or(CalculationError::AnimationNotFound))),
+            None => None
+        };
+
+        let mut colors = Vec::new();
+
+        for slot in self.source.slots.iter().flat_map(|s| s.iter()) {
+            let timelines = animation.and_then(|a| a.slots.as_ref())
+                                     .and_then(|s| s.get(&slot.name));
+
+            let anim_color = match timelines {
+                Some(timelines) => {
+                    let setup = try!(parse_optional_color(&slot.color));
+                    let elapsed = get_timeline_elapsed(&slot.name, get_slot_timeline_end(timelines),
+                                                       elapsed, options);
+                    try!(timelines_to_slotdata(timelines, elapsed, setup, options)).0
+                },
+                None => None
+            };
+
+            let color = match anim_color {
+                Some(c) => c,
+                None => try!(parse_optional_color(&slot.color)),
+            };
+
+            let color = tint_color(color, options);
+            let color = if options.premultiplied_alpha { premultiply_color(color) } else { color };
+            colors.push((&slot.name[..], color));
+        }
+
+        Ok(colors)
+    }
+
+    /// Calculates the position and the direction of a point of a path attachment.
+    ///
+    /// `distance` goes from 0 at the start of the path to 1 at its end. It is modulo'd by 1 if
+    ///  the path is closed, and clamped otherwise. If the path has constant speed, `distance`
+    ///  is spread over the curves of the path according to their lengths, but linearly inside
+    ///  of each curve, which is an approximation.
+    ///
+    /// The attachment is searched in `skin`, then in the default skin. Returns `Ok(None)` if
+    ///  the attachment is not a path, or if its vertices are weighted, which is not supported.
+    pub fn calculate_path_point<'a>(&'a self, skin: &str, animation: Option<&str>,
+        elapsed: f32, slot: &'a str, attachment: &'a str, distance: f32)
+        -> Result<Option<PathPoint>, CalculationError<'a>>
+    {
+        let path = try!(self.get_skin_attachment(skin, slot, attachment));
+        if path.type_ != Some(AttachmentType::Path) {
+            return Ok(None);
+        }
+
+        let vertices = match path.vertices {
+            Some(ref v) => v,
+            None => return Ok(None)
+        };
+
+        // weighted vertices have more than two numbers per vertex
+        let count = cmp::max(path.vertexCount.unwrap_or(0), 0) as usize;
+        if count < 3 || vertices.len() != count * 2 {
+            return Ok(None);
+        }
+
+        // each point of the path has a control point before and after it
+        let closed = path.closed.unwrap_or(false);
+        let curves = if closed { count / 3 } else { count / 3 - 1 };
+        if curves == 0 {
+            return Ok(None);
+        }
+
+        let matrix = try!(self.calculate_slot_matrix(animation, elapsed, slot));
+
+        // the curves are transformed by transforming their control points
+        let points: Vec<(f32, f32)> = vertices.chunks(2).map(|v| {
+            let (x, y) = (v[0] as f32, v[1] as f32);
+            (matrix.x.x * x + matrix.y.x * y + matrix.w.x,
+             matrix.x.y * x + matrix.y.y * y + matrix.w.y)
+        }).collect();
+
+        let distance = if closed {
+            distance - distance.floor()
+        } else {
+            distance.max(0.0).min(1.0)
+        };
+
+        // finding the curve and the position in this curve
+        let (curve, t) = match path.lengths {
+            Some(ref lengths) if path.constantSpeed.unwrap_or(true) && lengths.len() >= curves => {
+                // the lengths are the distances between the start of the path and the end of
+                // each curve
+                let target = distance * lengths[curves - 1] as f32;
+                let curve = lengths[.. curves].iter().position(|&l| target <= l as f32)
+                                              .unwrap_or(curves - 1);
+                let start = if curve == 0 { 0.0 } else { lengths[curve - 1] as f32 };
+                let length = lengths[curve] as f32 - start;
+                (curve, if length > 0.0 { (target - start) / length } else { 0.0 })
+            },
+            _ => {
+                let position = distance * curves as f32;
+                let curve = cmp::min(position as usize, curves - 1);
+                (curve, position - curve as f32)
+            }
+        };
+
+        let p0 = points[(curve * 3 + 1) % count];
+        let c1 = points[(curve * 3 + 2) % count];
+        let c2 = points[(curve * 3 + 3) % count];
+        let p1 = points[(curve * 3 + 4) % count];
+
+        let u = 1.0 - t;
+        let position = (u * u * u * p0.0 + 3.0 * u * u * t * c1.0 + 3.0 * u * t * t * c2.0
+                            + t * t * t * p1.0,
+                        u * u * u * p0.1 + 3.0 * u * u * t * c1.1 + 3.0 * u * t * t * c2.1
+                            + t * t * t * p1.1);
+        let tangent = (3.0 * u * u * (c1.0 - p0.0) + 6.0 * u * t * (c2.0 - c1.0)
+                           + 3.0 * t * t * (p1.0 - c2.0),
+                       3.0 * u * u * (c1.1 - p0.1) + 6.0 * u * t * (c2.1 - c1.1)
+                           + 3.0 * t * t * (p1.1 - c2.1));
+        let length = (tangent.0 * tangent.0 + tangent.1 * tangent.1).sqrt();
+        let tangent = if length > 0.0 { (tangent.0 / length, tangent.1 / length) } else { (1.0, 0.0) };
+
+        Ok(Some(PathPoint {
+            position: position,
+            tangent: tangent,
+        }))
+    }
+
+    /// Calculates the polygon of a bounding box attachment, for example for hit detection.
+    ///
+    /// The vertices are transformed by the bone of the slot, like the sprites of `calculate`.
+    ///
+    /// The attachment is searched in `skin`, then in the default skin. Returns `Ok(None)` if
+    ///  the attachment is not a bounding box, or if its vertices are weighted, which is not
+    ///  supported.
+    pub fn calculate_bounding_box<'a>(&'a self, skin: &str, animation: Option<&str>,
+        elapsed: f32, slot: &'a str, attachment: &'a str)
+        -> Result<Option<Vec<(f32, f32)>>, CalculationError<'a>>
+    {
+        let bounding_box = try!(self.get_skin_attachment(skin, slot, attachment));
+        if bounding_box.type_ != Some(AttachmentType::BoundingBox) {
+            return Ok(None);
+        }
+
+        let vertices = match bounding_box.vertices {
+            Some(ref v) => v,
+            None => return Ok(None)
+        };
+
+        // weighted vertices have more than two numbers per vertex
+        let count = bounding_box.vertexCount.map(|c| cmp::max(c, 0) as usize)
+                                            .unwrap_or(vertices.len() / 2);
+        if vertices.len() != count * 2 {
+            return Ok(None);
+        }
+
+        let matrix = try!(self.calculate_slot_matrix(animation, elapsed, slot));
+
+        Ok(Some(vertices.chunks(2).map(|v| {
+            let (x, y) = (v[0] as f32, v[1] as f32);
+            (matrix.x.x * x + matrix.y.x * y + matrix.w.x,
+             matrix.x.y * x + matrix.y.y * y + matrix.w.y)
+        }).collect()))
+    }
+
+    /// Finds an attachment of a slot in a skin, then in the default skin.
+    fn get_skin_attachment<'a>(&'a self, skin: &str, slot: &str, attachment: &'a str)
+        -> Result<&'a format::Attachment, CalculationError<'a>>
+    {
+        let skins = try!(self.source.skins.as_ref().ok_or(CalculationError::SkinNotFound));
+        if !skins.contains_key(skin) {
+            return Err(CalculationError::SkinNotFound);
+        }
+
+        skins.get(skin).and_then(|s| s.get(slot)).and_then(|s| s.get(attachment))
+             .or_else(|| skins.get("default").and_then(|s| s.get(slot))
+                              .and_then(|s| s.get(attachment)))
+             .ok_or(CalculationError::AttachmentNotFound(attachment))
+    }
+
+    /// Calculates the world matrix of the bone of a slot.
+    fn calculate_slot_matrix<'a>(&'a self, animation: Option<&str>, elapsed: f32, slot: &'a str)
+        -> Result<Matrix4<f32>, CalculationError<'a>>
+    {
+        let slot_bone = &try!(self.source.slots.iter().flat_map(|s| s.iter())
+                                  .find(|s| s.name == slot)
+                                  .ok_or(CalculationError::SlotNotFound(slot))).bone;
+        let bones = try!(self.calculate_bones(animation, elapsed));
+        let matrix = try!(bones.iter().find(|&&(name, _)| name == *slot_bone)
+                               .ok_or(CalculationError::BoneNotFound(slot_bone))).1;
+        Ok(matrix)
+    }
+
+    /// Loops `elapsed` over the duration of an animation with `loop_time`.
+    fn loop_elapsed(&self, animation: Option<&str>, elapsed: f32, options: &CalculationOptions)
+        -> Result<f32, CalculationError>
+    {
+        match animation.and_then(|a| self.get_animation_duration(a)) {
+            Some(duration) => loop_time(duration, elapsed, options),
+            None => Ok(elapsed)
+        }
+    }
+
+    /// Finds an attachment of a slot in `skin`, then in the default skin, then in the other
+    ///  skins in alphabetical order. Returns the name of the skin and of the attachment.
+    fn find_attachment(&self, skin: &str, slot: &str, attachment: &str) -> Option<(&str, &str)> {
+        let skins = match self.source.skins.as_ref() {
+            Some(s) => s,
+            None => return None
+        };
+
+        // the requested skin first, then the default skin, then the others by name
+        let mut candidates: Vec<_> = skins.iter().collect();
+        candidates.sort_by(|a, b| {
+            let rank = |name: &str| if name == skin { 0 } else if name == "default" { 1 } else { 2 };
+            (rank(&a.0[..]), a.0).cmp(&(rank(&b.0[..]), b.0))
+        });
+
+        for (skin_name, slots) in candidates.into_iter() {
+            let found = slots.get(slot).and_then(|a| a.iter().find(|&(n, _)| n == attachment));
+            if let Some((name, _)) = found {
+                return Some((&skin_name[..], &name[..]));
+            }
+        }
+
+        None
+    }
+
+    /// Returns the names of the regions of an attachment that has a sequence.
+    fn get_sequence_regions(&self, skin: &str, slot: &str, attachment: &str)
+        -> Option<&Vec<String>>
+    {
+        self.sequences.get(skin).and_then(|s| s.get(slot)).and_then(|s| s.get(attachment))
+    }
+
+    /// Returns a reference to the `format::Animation` with the given name.
+    fn get_animation(&self, name: &str) -> Option<&format::Animation> {
+        match self.source.animations.as_ref().and_then(|list| list.get(name)) {
+            None if name == EMPTY_ANIMATION => Some(&self.empty_animation),
+            animation => animation
+        }
+    }
+
+    /// Calculates the list of sprites that must be displayed and their matrix.
+    ///
+    /// The sprites are returned in draw order, which is the order of the slots in the document
+    ///  modified by the draw order timeline of the animation.
+    ///
+    /// If `elapsed` is longer than the duration of the animation, it will be modulo'd. This means
+    ///  that you can start an animation at any phase by adding an offset to `elapsed`, for
+    ///  example to prevent several characters playing the same animation from moving in sync.
+    // TODO: implement events
+    // TODO: implement other attachment types
+    pub fn calculate(&self, skin: &str, animation: Option<&str>, elapsed: f32)
+        -> Result<Calculation, CalculationError>
+    {
+        self.calculate_with_options(skin, animation, elapsed, &Default::default())
+    }
+
+    /// Same as `calculate`, but with additional options.
+    pub fn calculate_with_options(&self, skin: &str, animation: Option<&str>, elapsed: f32,
+        options: &CalculationOptions) -> Result<Calculation, CalculationError>
+    {
+        // adapting elapsed
+        let elapsed = try!(self.loop_elapsed(animation, elapsed, options));
+
+        self.calculate_at(skin, animation, elapsed, options)
+    }
+
+    /// Calculates `count` frames evenly spaced over the whole duration of an animation.
+    ///
+    /// The first frame is at time 0 and the last one is exactly at the end of the animation,
+    ///  which is what you want when exporting a sprite sheet. If `count` is 1, only the frame
+    ///  at time 0 is calculated. If `count` is 0, the result is empty.
+    pub fn calculate_frames(&self, skin: &str, animation: &str, count: usize)
+        -> Result<Vec<Calculation>, CalculationError>
+    {
+        self.calculate_frames_with_options(skin, animation, count, &Default::default())
+    }
+
+    /// Same as `calculate_frames`, but with additional options.
+    ///
+    /// The `range`, `loop_start` and `duration` of the options are ignored, since the frames
+    ///  always cover the whole animation.
+    pub fn calculate_frames_with_options(&self, skin: &str, animation: &str, count: usize,
+        options: &CalculationOptions) -> Result<Vec<Calculation>, CalculationError>
+    {
+        let duration = try!(self.get_animation_duration(animation)
+            .ok_or(CalculationError::AnimationNotFound));
+
+        let mut frames = Vec::with_capacity(count);
+        for index in 0 .. count {
+            let elapsed = get_frame_time(duration, index, count);
+
+            // not using `calculate_with_options` so that the last frame isn't wrapped to 0
+            frames.push(try!(self.calculate_at(skin, Some(animation), elapsed, options)));
+        }
+
+        Ok(frames)
+    }
+
+    /// Same as `calculate_frames`, but skips the frames that look the same as the last frame
+    ///  that was kept, the components of their matrices being allowed to differ by at most
+    ///  `epsilon`.
+    ///
+    /// Each frame is returned with its time, so that you can give each of them the right
+    ///  duration when exporting a sprite sheet. The first frame is always kept.
+    pub fn calculate_distinct_frames(&self, skin: &str, animation: &str, count: usize,
+        epsilon: f32) -> Result<Vec<(f32, Calculation)>, CalculationError>
+    {
+        let duration = try!(self.get_animation_duration(animation)
+            .ok_or(CalculationError::AnimationNotFound));
+
+        let mut frames: Vec<(f32, Calculation)> = Vec::new();
+        for index in 0 .. count {
+            let elapsed = get_frame_time(duration, index, count);
+            let frame = try!(self.calculate_at(skin, Some(animation), elapsed,
+                                               &Default::default()));
+
+            let same = frames.last().map(|&(_, ref last)| last.abs_diff_eq(&frame, epsilon))
+                             .unwrap_or(false);
+            if !same {
+                frames.push((elapsed, frame));
+            }
+        }
+
+        Ok(frames)
+    }
+
+    /// Calculates the sprites at the time of a keyframe of an animation.
+    ///
+    /// `index` is an index in the list returned by `get_animation_keyframe_times`, which
+    ///  contains the keyframes of all the timelines. The time is taken as is from the
+    ///  document, which means that the calculation never lands between two frames because of
+    ///  rounding, and that the last keyframe isn't wrapped to the start of the animation.
+    ///
+    /// Returns `None` if there is no keyframe at this index.
+    pub fn calculate_at_keyframe(&self, skin: &str, animation: &str, index: usize)
+        -> Result<Option<Calculation>, CalculationError>
+    {
+        let times = try!(self.get_animation_keyframe_times(animation)
+            .ok_or(CalculationError::AnimationNotFound));
+
+        match times.get(index) {
+            Some(&elapsed) => {
+                let calculation = try!(self.calculate_at(skin, Some(animation), elapsed,
+                                                         &Default::default()));
+                Ok(Some(calculation))
+            },
+            None => Ok(None)
+        }
+    }
+
+    /// Calculates `count` consecutive frames of an animation at the frame rate of the document,
+    ///  starting at time 0.
+    ///
+    /// This lets editors step through an animation frame by frame, as in Spine. The frame rate
+    ///  is 30 frames per second if the document doesn't specify it. The animation loops if the
+    ///  frames last longer than it.
+    pub fn calculate_frames_at_fps(&self, skin: &str, animation: &str, count: usize)
+        -> Result<Vec<Calculation>, CalculationError>
+    {
+        let fps = match self.get_fps() {
+            Some(fps) if fps > 0.0 => fps,
+            _ => 30.0
+        };
+
+        let mut frames = Vec::with_capacity(count);
+        for index in 0 .. count {
+            frames.push(try!(self.calculate(skin, Some(animation), index as f32 / fps)));
+        }
+
+        Ok(frames)
+    }
+
+    /// Calculates the sprites every `delta` seconds from the beginning to the end of an
+    ///  animation, and passes the time and the sprites of each frame to a callback.
+    ///
+    /// Contrary to `calculate_frames`, the frames are not kept in memory, which is useful when
+    ///  the sprites are directly pushed to a batch. Returns `InvalidFrameRate` if `delta` isn't
+    ///  strictly positive and finite, or if it gives more than `MAX_FRAMES` frames.
+    pub fn for_each_frame<F>(&self, skin: &str, animation: &str, delta: f32, mut callback: F)
+        -> Result<(), CalculationError> where F: FnMut(f32, &[Sprite])
+    {
+        let duration = try!(self.get_animation_duration(animation)
+            .ok_or(CalculationError::AnimationNotFound));
+        if !(delta > 0.0) || !delta.is_finite() {
+            return Err(CalculationError::InvalidFrameRate);
+        }
+        let count = try!(get_frame_count(duration / delta));
+
+        for index in 0 .. count {
+            let elapsed = index as f32 * delta;
+            let frame = try!(self.calculate_at(skin, Some(animation), elapsed,
+                                               &Default::default()));
+            callback(elapsed, &frame.sprites);
+        }
+
+        Ok(())
+    }
+
+    /// Calculates the sprites `fps` times per second from the beginning to the end of an
+    ///  animation, and returns them in a single list along with the range of each frame in
+    ///  this list.
+    ///
+    /// The sprites of frame `k` are `sprites[ranges[k].clone()]`. This is more compact and
+    ///  more cache-friendly than a list of `Calculation`s when all the frames are calculated in
+    ///  advance. Returns `InvalidFrameRate` if `fps` isn't strictly positive and finite, or if
+    ///  it gives more than `MAX_FRAMES` frames.
+    pub fn calculate_flat_frames(&self, skin: &str, animation: &str, fps: f32)
+        -> Result<(Vec<Sprite>, Vec<Range<usize>>), CalculationError>
+    {
+        let duration = try!(self.get_animation_duration(animation)
+            .ok_or(CalculationError::AnimationNotFound));
+
+        if !(fps > 0.0) || !fps.is_finite() {
+            return Err(CalculationError::InvalidFrameRate);
+        }
+        let count = try!(get_frame_count(duration * fps));
+
+        let mut sprites = Vec::new();
+        let mut ranges = Vec::with_capacity(count);
+        for index in 0 .. count {
+            let elapsed = index as f32 / fps;
+            let frame = try!(self.calculate_at(skin, Some(animation), elapsed,
+                                               &Default::default()));
+            let start = sprites.len();
+            sprites.extend(frame.sprites.into_iter());
+            ranges.push(start .. sprites.len());
+        }
+
+        Ok((sprites, ranges))
+    }
+
+    /// Calculates the sprites at the given time, without looping the animation.
+    fn calculate_at(&self, skin: &str, animation: Option<&str>, elapsed: f32,
+        options: &CalculationOptions) -> Result<Calculation, CalculationError>
+    {
+        let layers = match animation {
+            Some(animation) => vec![Layer {
+                animation: animation,
+                elapsed: elapsed,
+                mix: None,
+                alpha: 1.0,
+            }],
+            None => Vec::new()
+        };
+
+        self.calculate_layers(skin, &layers, options)
+    }
+
+    /// Calculates the sprites of several animations applied over each other.
+    ///
+    /// Each layer replaces the bones and slots animated by its animation, weighted by its alpha.
+    ///  The draw order and the sequences come from the highest layer that animates them.
+    fn calculate_layers<'a>(&'a self, skin: &str, layers: &[Layer],
+        options: &CalculationOptions) -> Result<Calculation<'a>, CalculationError<'a>>
+    {
+        let skin_name = skin;
+
+        // getting a reference to the `format::Skin`
+        let skin = try!(self.source.skins.as_ref().and_then(|l| l.get(skin))
+            .ok_or(CalculationError::SkinNotFound));
+
+        // getting a reference to "default" skin
+        let default_skin = try!(self.source.skins.as_ref().and_then(|l| l.get("default"))
+            .ok_or(CalculationError::SkinNotFound));
+
+        // getting a reference to the `format::Animation` of each layer
+        let mut animations: Vec<(&format::Animation, f32)> = Vec::with_capacity(layers.len());
+        for layer in layers.iter() {
+            animations.push((try!(self.get_animation(layer.animation)
+                                      .ok_or(CalculationError::AnimationNotFound)),
+                             layer.elapsed));
+        }
+
+        // calculating the relative transformation of all bones, starting from the setup pose
+        let mut bones = try!(self.calculate_bones_local(None, 0.0, options));
+
+        for (layer, &(animation, elapsed)) in layers.iter().zip(animations.iter()) {
+            let mut layer_bones = try!(self.calculate_bones_local(Some(animation), elapsed,
+                                                                  options));
+            let mut from_animation = None;
+
+            // crossfading with the previous animation of the layer
+            if let Some((from, from_elapsed, weight)) = layer.mix {
+                let from = try!(self.get_animation(from)
+                                    .ok_or(CalculationError::AnimationNotFound));
+                let from_bones = try!(self.calculate_bones_local(Some(from), from_elapsed,
+                                                                 options));
+
+                for (&mut (_, ref mut data), &(_, ref from_data)) in
+                    layer_bones.iter_mut().zip(from_bones.iter())
+                {
+                    *data = from_data.mix(data, weight);
+                }
+
+                from_animation = Some(from);
+            }
+
+            // replacing the bones animated by this layer
+            let animates = |a: &format::Animation, bone: &String| {
+                a.bones.as_ref().map(|b| b.contains_key(bone)).unwrap_or(false)
+            };
+
+            for (&mut (bone, ref mut data), &(_, ref layer_data)) in
+                bones.iter_mut().zip(layer_bones.iter())
+            {
+                if !animates(animation, &bone.name) &&
+                   !from_animation.map(|a| animates(a, &bone.name)).unwrap_or(false)
+                {
+                    continue;
+                }
+
+                *data = if layer.alpha >= 1.0 {
+                    layer_data.clone()
+                } else {
+                    data.mix(layer_data, layer.alpha)
+                };
+            }
+        }
+
+        apply_bone_overrides(&mut bones, options);
+
+        // calculating the world matrix of all bones
+        let mut bones = self.bones_local_to_world(bones);
+        apply_root_transform(&mut bones, options);
+
+        // now taking each slot in the document and matching its bone
+        // `slots` contains the slot name, bone data, color, and attachment
+        let mut slots: Vec<(&str, Matrix4<f32>, Rgba<u8>, Option<&str>)> =
+            if let Some(slots) = self.source.slots.as_ref() {
+                let mut result = Vec::new();
+
+                for slot in slots.iter() {
+                    let bone = try!(bones.iter().find(|&&(name, _)| name == slot.bone)
+                        .ok_or(CalculationError::BoneNotFound(&slot.bone)));
+                    let color = match slot.color {
+                        Some(ref c) => try!(parse_color(c)),
+                        None => Rgba { a: 255, c: Rgb::new(255, 255, 255) }
+                    };
+                    result.push((&slot.name[..], bone.1, color,
+                        slot.attachment.as_ref().map(|s| &s[..])))
+                }
+
+                result
+
+            } else {
+                Vec::new()
+            };
+
+        // replacing the values by the ones overridden by the animation of each layer
+        for (layer, &(animation, elapsed)) in layers.iter().zip(animations.iter()) {
+            // the colors of the previous animation of the layer are faded out towards the ones
+            // of the current animation, or towards the setup pose if it doesn't animate them
+            let from = match layer.mix {
+                Some((from, from_elapsed, weight)) => {
+                    let from = try!(self.get_animation(from)
+                                        .ok_or(CalculationError::AnimationNotFound));
+                    from.slots.as_ref().map(|s| (s, from_elapsed, weight))
+                },
+                None => None
+            };
+
+            for &mut (slot_name, _, ref mut color, ref mut attachment) in slots.iter_mut() {
+                let timelines = animation.slots.as_ref().and_then(|s| s.get(slot_name));
+                let from_timelines = from.and_then(|(s, from_elapsed, weight)| {
+                    s.get(slot_name).map(|t| (t, from_elapsed, weight))
+                });
+
+                // calculating the variation from the animation
+                let (anim_color, anim_attach) = match timelines {
+                    Some(timelines) => {
+                        let elapsed = get_timeline_elapsed(slot_name,
+                                                           get_slot_timeline_end(timelines),
+                                                           elapsed, options);
+                        try!(timelines_to_slotdata(timelines, elapsed, color.clone(), options))
+                    },
+                    None => (None, None)
+                };
+
+                let anim_color = match from_timelines {
+                    Some((timelines, from_elapsed, weight)) => {
+                        let from_elapsed = get_timeline_elapsed(slot_name,
+                                                                get_slot_timeline_end(timelines),
+                                                                from_elapsed, options);
+                        match try!(timelines_to_slotdata(timelines, from_elapsed, color.clone(),
+                                                         options)).0
+                        {
+                            Some(from_color) => {
+                                let to = anim_color.unwrap_or(color.clone());
+                                Some(mix_color(from_color, to, weight))
+                            },
+                            None => anim_color
+                        }
+                    },
+                    None => anim_color
+                };
+
+                // replacing the values in the `slots` vec above
+                // an attachment keyframe without a name hides the slot
+                if let Some(c) = anim_color {
+                    *color = if layer.alpha >= 1.0 {
+                        c
+                    } else {
+                        mix_color(color.clone(), c, layer.alpha.max(0.0))
+                    };
+                }
+                if let Some(a) = anim_attach {
+                    if !options.freeze_attachments { *attachment = a };
+                }
+            }
+        }
+
+        // applying the attachments overridden by the user, which can come from any skin
+        let mut overridden_skins: Vec<Option<&str>> = slots.iter().map(|_| None).collect();
+        for (&mut (slot_name, _, _, ref mut attachment), overridden_skin) in
+            slots.iter_mut().zip(overridden_skins.iter_mut())
+        {
+            match (options.attachment_overrides.get(slot_name),
+                   options.attachment_override_skins.get(slot_name))
+            {
+                (Some(&Some(ref name)), Some(requested)) => {
+                    let (skin, attachments) = try!(self.source.skins.as_ref()
+                        .and_then(|s| s.iter().find(|&(n, _)| n == requested))
+                        .ok_or(CalculationError::SkinNotFound));
+                    let found = try!(attachments.get(slot_name)
+                        .and_then(|a| a.keys().find(|n| *n == name))
+                        .ok_or_else(|| CalculationError::OverrideAttachmentNotFound {
+                            skin: skin,
+                            slot: slot_name,
+                            attachment: name.clone(),
+                        }));
+                    *attachment = Some(&found[..]);
+                    *overridden_skin = Some(&skin[..]);
+                },
+                (Some(&Some(ref name)), None) => {
+                    match self.find_attachment(skin_name, slot_name, name) {
+                        Some((skin, name)) => {
+                            *attachment = Some(name);
+                            *overridden_skin = Some(skin);
+                        },
+                        None => *attachment = None
+                    }
+                },
+                (Some(&None), _) => *attachment = None,
+                (None, _) => ()
+            }
+        }
+
+        // calculating the order in which the slots must be drawn
+        let draw_order = {
+            let draworder = animations.iter().rev()
+                                      .filter_map(|&(a, elapsed)| a.draworder.as_ref()
+                                                                   .map(|d| (d, elapsed)))
+                                      .next();
+
+            try!(get_draw_order(self.source.slots.as_ref().map(|s| &s[..]).unwrap_or(&[]),
+                                draworder.map(|d| d.0), draworder.map(|d| d.1).unwrap_or(0.0)))
+        };
+
+        // now finding the attachment of each slot
+        let slots = {
+            let mut results = Vec::new();
+
+            for &slot_index in draw_order.iter() {
+                let (slot_name, bone_data, color, attachment) = slots[slot_index].clone();
+
+                if let Some(attachment) = attachment {
+                    let (attachments_skin, attachments) = match overridden_skins[slot_index] {
+                        Some(overridden_skin) => {
+                            match self.source.skins.as_ref().and_then(|s| s.get(overridden_skin))
+                                                            .and_then(|s| s.get(slot_name))
+                            {
+                                Some(a) => (overridden_skin, a),
+                                None => continue
+                            }
+                        },
+                        None => match skin.get(slot_name) {
+                            Some(a) => (skin_name, a),
+                            None => match default_skin.get(slot_name) {
+                                Some(a) => ("default", a),
+                                None => continue
+                            }
+                        }
+                    };
+
+                    let attachment = try!(attachments.iter()
+                        .find(|&(a, _)| a == attachment)
+                        .ok_or(CalculationError::AttachmentNotFound(attachment)));
+
+                    let attachment_transform = get_attachment_transformation(attachment.1);
+                    let bone_data = bone_data * attachment_transform;
+
+                    // attachments with a sequence display one of their regions
+                    let regions = self.get_sequence_regions(attachments_skin, slot_name,
+                                                            attachment.0);
+                    let name = match (regions, attachment.1.sequence.as_ref()) {
+                        (Some(regions), Some(sequence)) => {
+                            let timeline = animations.iter().rev().filter_map(|&(a, elapsed)| {
+                                a.attachments.as_ref()
+                                 .and_then(|a| a.get(attachments_skin))
+                                 .and_then(|a| a.get(slot_name))
+                                 .and_then(|a| a.get(attachment.0))
+                                 .and_then(|a| a.sequence.as_ref())
+                                 .map(|t| (t, elapsed))
+                            }).next();
+                            let index = get_sequence_index(sequence, timeline.map(|t| t.0),
+                                                           timeline.map(|t| t.1).unwrap_or(0.0),
+                                                           options.random_seed);
+                            match regions.get(index) {
+                                Some(region) => &region[..],
+                                None => continue
+                            }
+                        },
+                        _ => get_sprite_name(attachment.0, attachment.1)
+                    };
+
+                    let color = tint_color(color, options);
+
+                    let color = if options.premultiplied_alpha {
+                        premultiply_color(color)
+                    } else {
+                        color
+                    };
+
+                    results.push(Sprite {
+                        name: name,
+                        slot: slot_name,
+                        matrix: bone_data,
+                        color: color,
+                        attachment_type: attachment.1.type_.clone()
+                                                     .unwrap_or(AttachmentType::Region),
+                    });
+                }
+            }
+
+            results
+        };
+
+        // final result
+        Ok(Calculation {
+            sprites: slots
+        })
+    }
+
+    /// Calculates the world matrix of each bone, in the order of the document.
+    fn calculate_bones_matrices<'a>(&'a self, animation: Option<&'a format::Animation>,
+        elapsed: f32, options: &CalculationOptions)
+        -> Result<Vec<(&'a str, Matrix4<f32>)>, CalculationError<'a>>
+    {
+        let mut bones = try!(self.calculate_bones_local(animation, elapsed, options));
+        apply_bone_overrides(&mut bones, options);
+        let mut bones = self.bones_local_to_world(bones);
+        apply_root_transform(&mut bones, options);
+        Ok(bones)
+    }
+
+    /// Calculates the transformation of each bone relative to its parent, without the
+    ///  overrides of the options.
+    fn calculate_bones_local<'a>(&'a self, animation: Option<&'a format::Animation>,
+        elapsed: f32, options: &CalculationOptions)
+        -> Result<Vec<(&'a format::Bone, BoneData)>, CalculationError<'a>>
+    {
+        // calculating the default pose of all bones
+        let mut bones: Vec<(&format::Bone, BoneData)> = self.source.bones.as_ref().map(|bones| {
+            bones.iter().map(|bone| (bone, get_bone_default_local_setup(bone))).collect()
+        }).unwrap_or_else(|| Vec::new());
+
+        // if we are animating, adding to the default pose the calculations from the animation
+        if let Some(animation) = animation {
+            if let Some(anim_bones) = animation.bones.as_ref() {
+                for (bone_name, timelines) in anim_bones.iter() {
+                    // calculating the variation from the animation
+                    let elapsed = get_timeline_elapsed(bone_name, get_bone_timeline_end(timelines),
+                                                       elapsed, options);
+                    let anim_data = try!(timelines_to_bonedata(timelines, elapsed, options));
+
+                    // adding this to the `bones` vec above
+                    match bones.iter_mut().find(|&&mut (b, _)| b.name == *bone_name) {
+                        Some(&mut (_, ref mut data)) => { *data = data.clone() + anim_data; },
+                        None => ()
+                    };
+                }
+            }
+        };
+
+        Ok(bones)
+    }
+
+    /// Turns the relative transformation of each bone into world matrices.
+    fn bones_local_to_world<'a>(&'a self, bones: Vec<(&'a format::Bone, BoneData)>)
+        -> Vec<(&'a str, Matrix4<f32>)>
+    {
+        // now we have our list of bones with their relative positions
+        // adding the position of the parent to each bone
+        bones.iter().map(|&(ref bone, ref relative_data)| {
+            let mut current_matrix = relative_data.to_matrix();
+            let mut current_parent = bone.parent.as_ref();
+
+            loop {
+                if let Some(parent_name) = current_parent {
+                    assert!(parent_name != &bone.name);     // prevent infinite loop
+
+                    match bones.iter().find(|&&(b, _)| b.name == *parent_name) {
+                        Some(ref p) => {
+                            current_parent = p.0.parent.as_ref();
+                            current_matrix = p.1.to_matrix() * current_matrix;
+                        },
+                        None => {
+                            current_parent = None;  // TODO: return BoneNotFound(parent_name);
+                        }
+                    }
+
+                } else {
+                    break
+                }
+            }
+
+            (&bone.name[..], current_matrix.clone())
+
+        }).collect()
+    }
+}
+
+/// Additional parameters of a calculation.
+///
+/// Use `CalculationOptions::default()` and modify the members that you need.
+#[derive(Debug, Clone, Default)]
+pub struct CalculationOptions {
+    /// Bones whose local transformation is overridden after the animation has been applied.
+    ///
+    /// The children of these bones inherit the overridden transformation.
+    pub bone_overrides: HashMap<String, BoneOverride>,
+
+    /// If true, the red, green and blue components of the colors of the sprites are multiplied
+    ///  by their alpha component. Defaults to false.
+    ///
+    /// Use this if your renderer blends with premultiplied alpha.
+    pub premultiplied_alpha: bool,
+
+    /// Easing functions that can be used as the "curve" of keyframes, in addition to
+    ///  "linear", "stepped" and bezier curves.
+    ///
+    /// The function receives the position between the two keyframes, from 0 to 1, and must
+    ///  return the factor to apply to the difference between their values.
+    pub curve_functions: HashMap<String, fn(f32) -> f32>,
+
+    /// If set, only the part of the animation between these two times is played. `elapsed`
+    ///  is then relative to the start of the range and is modulo'd by its length.
+    ///
+    /// The times are clamped to the duration of the animation. The calculation returns
+    ///  `InvalidRange` if the start isn't before the end.
+    pub range: Option<(f32, f32)>,
+
+    /// If set, the animation is played once from its start, then loops from this time instead
+    ///  of 0. This is useful for animations that have an intro that must not be repeated.
+    ///
+    /// If `range` is set too, the loop start must be inside of the range. The calculation
+    ///  returns `InvalidRange` if the loop start is not before the end of the animation.
+    pub loop_start: Option<f32>,
+
+    /// If set, the animation is played in this number of seconds instead of its own duration,
+    ///  for example to synchronize a walk cycle with the speed of a character.
+    ///
+    /// `elapsed` is multiplied by the duration of the animation divided by this one before
+    ///  being looped, so the range and the loop start are still in the time of the animation.
+    ///  The calculation returns `InvalidRange` if this duration isn't strictly positive.
+    pub duration: Option<f32>,
+
+    /// Seed of the regions chosen by the sequences in "random" mode. Defaults to 0.
+    ///
+    /// The same seed always gives the same regions at the same time, which means that replays
+    ///  and networked clients agree. Use different seeds for characters that must not look
+    ///  the same.
+    pub random_seed: u64,
+
+    /// Bones and slots whose timelines loop over their own duration, ie. the time of their
+    ///  last keyframe, instead of keeping their last value until the end of the animation.
+    ///
+    /// This lets a single animation contain a short looping part, for example a walk cycle,
+    ///  and a longer part that is played once. The animation itself still loops over its
+    ///  whole duration. Empty by default.
+    pub looping_timelines: HashSet<String>,
+
+    /// Transformation applied to the world matrix of every bone, and thus of every sprite.
+    ///
+    /// This lets you place, scale or flip a whole skeleton, and bake this placement into the
+    ///  matrices returned by `calculate_frames_with_options` so that nothing is left to do at
+    ///  runtime.
+    pub root_transform: Option<Matrix4<f32>>,
+
+    /// Color that the color of every sprite is multiplied with, for example to flash a
+    ///  character in red when it is hit.
+    ///
+    /// The tint is applied after the colors of the slots, and before premultiplying the
+    ///  alpha. A white tint has no effect.
+    pub tint: Option<Rgba<u8>>,
+
+    /// Factor that the alpha of every sprite is multiplied with, between 0 and 1, for example
+    ///  to fade a whole character out when it dies. `None` has the same effect as 1.
+    ///
+    /// Like the tint, it is applied after the colors of the slots and before premultiplying
+    ///  the alpha.
+    pub alpha: Option<f32>,
+
+    /// If true, the attachment timelines are ignored and each slot shows its attachment of the
+    ///  setup pose. The bones and the colors are still animated. Defaults to false.
+    ///
+    /// This is a debugging aid to find out whether a glitch comes from the bones or from an
+    ///  attachment being switched.
+    pub freeze_attachments: bool,
+
+    /// Attachments shown by some slots whatever the animation, for example to equip items
+    ///  without creating a skin. The keys are slot names.
+    ///
+    /// The attachment is searched in the skin of `attachment_override_skins` if any, otherwise
+    ///  in the skin being calculated, then in the default skin, then in the other skins. A
+    ///  value of `None`, or an attachment that is not found in any skin, hides the slot.
+    ///  Remove the entry of a slot to restore its normal behavior.
+    pub attachment_overrides: HashMap<String, Option<String>>,
+
+    /// Skins from which the attachments of `attachment_overrides` are taken, instead of
+    ///  searching all the skins. The keys are slot names.
+    ///
+    /// This is useful when the same attachment name exists in several skins. Calculations
+    ///  return `SkinNotFound` if the skin doesn't exist, and `OverrideAttachmentNotFound` if
+    ///  the skin doesn't have the attachment for this slot.
+    pub attachment_override_skins: HashMap<String, String>,
+}
+
+/// Replaces parts of the transformation of a bone relative to its parent.
+///
+/// The members that are `None` keep the value calculated from the animation.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BoneOverride {
+    /// Position relative to the parent.
+    pub position: Option<(f32, f32)>,
+
+    /// Rotation in degrees relative to the parent.
+    pub rotation: Option<f32>,
+
+    /// Scale relative to the parent.
+    pub scale: Option<(f32, f32)>,
+}
+
+/// Result of an animation state calculation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Calculation<'a> {
+    /// The list of sprites that should be drawn.
+    ///
+    /// The elements are sorted from bottom to top, ie. each element can cover the previous one.
+    pub sprites: Vec<Sprite<'a>>,
+}
+
+impl<'a> Calculation<'a> {
+    /// Builds a `Pose` that doesn't borrow the document.
+    pub fn to_pose(&self) -> Pose {
+        Pose {
+            sprites: self.sprites.iter().map(|sprite| {
+                PoseSprite {
+                    name: sprite.name.to_string(),
+                    slot: sprite.slot.to_string(),
+                    matrix: sprite.matrix.clone(),
+                    color: [sprite.color.c.r, sprite.color.c.g, sprite.color.c.b, sprite.color.a],
+                    attachment_type: sprite.attachment_type.clone(),
+                }
+            }).collect()
+        }
+    }
+
+    /// Returns true if both calculations have the same sprites, allowing the components of
+    ///  their matrices to differ by at most `epsilon`.
+    pub fn abs_diff_eq(&self, other: &Calculation, epsilon: f32) -> bool {
+        self.sprites.len() == other.sprites.len() &&
+            self.sprites.iter().zip(other.sprites.iter()).all(|(a, b)| a.abs_diff_eq(b, epsilon))
+    }
+
+    /// Interpolates between this calculation and the next frame, `weight` going from 0 for
+    ///  this one to 1 for `other`.
+    ///
+    /// This gives a smooth playback of frames calculated in advance with a low frame rate,
+    ///  for example with `calculate_frames`, when they are displayed with a higher one. The
+    ///  axes of the matrices are rotated along the shortest arc and the colors are mixed. The
+    ///  sprites and their order are those of the nearest frame, and a sprite is only
+    ///  interpolated if the other frame draws the same sprite with the same slot.
+    ///
+    /// Storing frames costs memory and the interpolation is only an approximation of the
+    ///  animation between them, but it is much cheaper than calculating each displayed frame.
+    pub fn interpolate(&self, other: &Calculation<'a>, weight: f32) -> Calculation<'a> {
+        let (nearest, farthest, weight) = if weight < 0.5 {
+            (self, other, weight)
+        } else {
+            (other, self, 1.0 - weight)
+        };
+
+        Calculation {
+            sprites: nearest.sprites.iter().map(|sprite| {
+                match farthest.sprites.iter().find(|s| s.slot == sprite.slot &&
+                                                        s.name == sprite.name)
+                {
+                    Some(far) => Sprite {
+                        name: sprite.name,
+                        slot: sprite.slot,
+                        matrix: mix_matrix(&sprite.matrix, &far.matrix, weight),
+                        color: mix_color(sprite.color.clone(), far.color.clone(), weight),
+                        attachment_type: sprite.attachment_type.clone(),
+                    },
+                    None => sprite.clone()
+                }
+            }).collect()
+        }
+    }
+
+    /// Returns the convex hull of the corners of the sprites, in counter-clockwise order.
+    ///
+    /// This is a tighter shape than a bounding box, for example to draw a selection outline or
+    ///  to build a simple collider. Only the sprites of regions and meshes are included, since
+    ///  the others are not supposed to be drawn. The corners that are infinite or NaN, for
+    ///  example because of a `BoneOverride`, are ignored. Returns an empty list if there is no
+    ///  sprite.
+    pub fn get_convex_hull(&self) -> Vec<(f32, f32)> {
+        let mut points: Vec<(f32, f32)> = Vec::with_capacity(self.sprites.len() * 4);
+
+        for sprite in self.sprites.iter() {
+            match sprite.attachment_type {
+                AttachmentType::Region | AttachmentType::RegionSequence |
+                AttachmentType::Mesh | AttachmentType::LinkedMesh => (),
+                _ => continue
+            }
+
+            let m = &sprite.matrix;
+            for &(x, y) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)].iter() {
+                let point = (m.x.x * x + m.y.x * y + m.w.x, m.x.y * x + m.y.y * y + m.w.y);
+                if point.0.is_finite() && point.1.is_finite() {
+                    points.push(point);
+                }
+            }
+        }
+
+        points.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        points.dedup();
+
+        if points.len() < 3 {
+            return points;
+        }
+
+        // Andrew's monotone chain: building the lower hull from left to right, then the upper
+        // hull from right to left
+        let cross = |o: (f32, f32), a: (f32, f32), b: (f32, f32)| {
+            (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+        };
+
+        let mut hull: Vec<(f32, f32)> = Vec::with_capacity(points.len() + 1);
+        for &p in points.iter() {
+            while hull.len() >= 2 &&
+                  cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+
+        let lower_len = hull.len() + 1;
+        for &p in points.iter().rev().skip(1) {
+            while hull.len() >= lower_len &&
+                  cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+
+        // the last point is the same as the first one
+        hull.pop();
+        hull
+    }
+}
+
+/// A sprite that must be drawn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sprite<'a> {
+    /// Name of the sprite.
+    ///
+    /// This is the name that you have in the Spine editor, which is not necessarily a file name.
+    pub name: &'a str,
+
+    /// Name of the slot that draws the sprite.
+    ///
+    /// This lets you find which sprite corresponds to a slot, for example for picking.
+    pub slot: &'a str,
+
+    /// Matrix of the sprite.
+    ///
+    /// The matrix assumes that the sprite is displayed from (-1, -1) to (1, 1), ie. would cover
+    ///  the whole screen.
+    pub matrix: Matrix4<f32>,
+
+    /// Color to multiply the sprite with.
+    pub color: Rgba<u8>,
+
+    /// Type of the attachment that produced this sprite.
+    ///
+    /// Sprites of bounding boxes are not supposed to be drawn.
+    pub attachment_type: AttachmentType,
+}
+
+impl<'a> Sprite<'a> {
+    /// Returns true if both sprites are the same, allowing the components of their matrices to
+    ///  differ by at most `epsilon`.
+    ///
+    /// This is useful for tests, as the matrices are the result of floating-point calculations.
+    pub fn abs_diff_eq(&self, other: &Sprite, epsilon: f32) -> bool {
+        let columns = [(&self.matrix.x, &other.matrix.x), (&self.matrix.y, &other.matrix.y),
+                       (&self.matrix.z, &other.matrix.z), (&self.matrix.w, &other.matrix.w)];
+
+        self.name == other.name && self.slot == other.slot && self.color == other.color &&
+            self.attachment_type == other.attachment_type &&
+            columns.iter().all(|&(a, b)| {
+                (a.x - b.x).abs() <= epsilon && (a.y - b.y).abs() <= epsilon &&
+                (a.z - b.z).abs() <= epsilon && (a.w - b.w).abs() <= epsilon
+            })
+    }
+}
+
+/// Snapshot of a calculation that doesn't borrow the document.
+///
+/// A pose can be serialized, for example in order to be sent over the network and drawn
+///  somewhere else.
+#[derive(Debug, Clone, PartialEq, RustcEncodable, RustcDecodable)]
+pub struct Pose {
+    /// The list of sprites that should be drawn, in the same order as in the `Calculation`.
+    pub sprites: Vec<PoseSprite>,
+}
+
+impl Pose {
+    /// Builds back the `Calculation` corresponding to this pose.
+    pub fn to_calculation(&self) -> Calculation {
+        Calculation {
+            sprites: self.sprites.iter().map(|sprite| {
+                let color = Rgba {
+                    a: sprite.color[3],
+                    c: Rgb::new(sprite.color[0], sprite.color[1], sprite.color[2]),
+                };
+
+                Sprite {
+                    name: &sprite.name[..],
+                    slot: &sprite.slot[..],
+                    matrix: sprite.matrix.clone(),
+                    color: color,
+                    attachment_type: sprite.attachment_type.clone(),
+                }
+            }).collect()
+        }
+    }
+}
+
+/// A sprite of a `Pose`.
+#[derive(Debug, Clone, PartialEq, RustcEncodable, RustcDecodable)]
+pub struct PoseSprite {
+    /// Name of the sprite.
+    pub name: String,
+
+    /// Name of the slot that draws the sprite.
+    pub slot: String,
+
+    /// Matrix of the sprite.
+    pub matrix: Matrix4<f32>,
+
+    /// Color of the sprite, in the RGBA order.
+    pub color: [u8; 4],
+
+    /// Type of the attachment that produced this sprite.
+    pub attachment_type: AttachmentType,
+}
+
+/// A point of a path attachment, in the same coordinates as the matrices of the bones.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PathPoint {
+    /// Position of the point.
+    pub position: (f32, f32),
+
+    /// Direction of the path at this point, as a vector of length 1.
+    pub tangent: (f32, f32),
+}
+
+/// An animation of a document, returned by `SpineDocument::get_animation_handle`.
+///
+/// The handle is cheap to copy, so you can keep one per character that plays the animation.
+#[derive(Clone, Copy)]
+pub struct AnimationHandle<'a> {
+    /// Document that contains the animation.
+    document: &'a SpineDocument,
+
+    /// Name of the animation.
+    name: &'a str,
+
+    /// Duration of the animation, measured when the handle was created.
+    duration: f32,
+}
+
+impl<'a> AnimationHandle<'a> {
+    /// Returns the name of the animation.
+    pub fn get_name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Returns the duration of the animation.
+    pub fn get_duration(&self) -> f32 {
+        self.duration
+    }
+
+    /// Same as `SpineDocument::calculate` with this animation.
+    pub fn calculate(&self, skin: &str, elapsed: f32)
+        -> Result<Calculation<'a>, CalculationError<'a>>
+    {
+        self.calculate_with_options(skin, elapsed, &Default::default())
+    }
+
+    /// Same as `SpineDocument::calculate_with_options` with this animation.
+    pub fn calculate_with_options(&self, skin: &str, elapsed: f32, options: &CalculationOptions)
+        -> Result<Calculation<'a>, CalculationError<'a>>
+    {
+        let elapsed = try!(loop_time(self.duration, elapsed, options));
+        self.document.calculate_at(skin, Some(self.name), elapsed, options)
+    }
+}
+
+/// An event of the document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event<'a> {
+    /// Name of the event.
+    pub name: &'a str,
+
+    /// Integer value attached to the event.
+    pub int: i32,
+
+    /// Floating-point value attached to the event.
+    pub float: f32,
+
+    /// String value attached to the event.
+    pub string: Option<&'a str>,
+
+    /// Path of the sound to play when the event is fired.
+    pub audio: Option<&'a str>,
+
+    /// Volume of the sound, between 0 and 1. Defaults to 1.
+    pub volume: f32,
+
+    /// Stereo balance of the sound, between -1 (left) and 1 (right). Defaults to 0.
+    pub balance: f32,
+}
+
+/// How a timeline of an animation is sampled at a given time.
+///
+/// Returned by `SpineDocument::get_timelines_sampling`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimelineSampling<'a> {
+    /// Name of the bone or slot animated by the timeline.
+    pub target: &'a str,
+
+    /// Kind of the timeline: "translate", "rotate", "scale", "attachment", "color", "rgb" or
+    ///  "alpha".
+    pub timeline: &'static str,
+
+    /// Position of the time relative to the keyframes of the timeline.
+    pub position: SamplingPosition,
+}
+
+/// Position of a time relative to the keyframes of a timeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SamplingPosition {
+    /// The time is before the first keyframe. The value is not interpolated.
+    BeforeFirstKeyframe,
+
+    /// The time is between two keyframes and the value is interpolated between them.
+    Interpolated,
+
+    /// The time is after the last keyframe, whose value is kept.
+    AfterLastKeyframe,
+}
+
+/// Error that can happen while loading a document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LoadError {
+    /// The document is not valid JSON.
+    Json(String),
+
+    /// The document is valid JSON, but something in it was not recognized.
+    Format(String),
+
+    /// A number in the document is infinite or NaN, or is outside of its range, like a
+    ///  sequence delay that isn't strictly positive or a sequence with too many regions.
+    ///
+    /// Contains a description of the element where the number was found.
+    InvalidNumber(String),
+
+    /// A bone referenced in the document doesn't exist.
+    BoneNotFound(String),
+
+    /// A slot referenced in the document doesn't exist.
+    SlotNotFound(String),
+
+    /// The source mesh of a linked mesh doesn't exist.
+    AttachmentNotFound(String),
+
+    /// Reading from the reader failed.
+    ///
+    /// Contrary to the other errors, this doesn't mean that the document is invalid, and
+    ///  loading it again may succeed. Contains the kind and the description of the error.
+    Io(io::ErrorKind, String),
+
+    /// The cache given to `read_cache` is corrupted or was written by another version of this
+    ///  library.
+    InvalidCache,
+
+    /// The scale given to `new_scaled` is 0, infinite or NaN.
+    InvalidScale,
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &LoadError::Json(ref e) => write!(formatter, "invalid JSON: {}", e),
+            &LoadError::Format(ref e) => write!(formatter, "unrecognized Spine document: {}", e),
+            &LoadError::InvalidNumber(ref e) => write!(formatter, "invalid number in {}", e),
+            &LoadError::BoneNotFound(ref e) => write!(formatter, "bone `{}` not found", e),
+            &LoadError::SlotNotFound(ref e) => write!(formatter, "slot `{}` not found", e),
+            &LoadError::AttachmentNotFound(ref e) => {
+                write!(formatter, "attachment `{}` not found", e)
+            },
+            &LoadError::Io(_, ref e) => write!(formatter, "read error: {}", e),
+            &LoadError::InvalidCache => write!(formatter, "invalid or outdated cache"),
+            &LoadError::InvalidScale => write!(formatter, "invalid scale"),
+        }
+    }
+}
+
+/// Error that can happen while calculating an animation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CalculationError<'a> {
+    /// The requested skin was not found.
+    SkinNotFound,
+
+    /// The requested animation was not found.
+    AnimationNotFound,
+
+    /// The requested bone was not found in the list of bones.
+    ///
+    /// This probably means that the Spine document contains an error.
+    BoneNotFound(&'a str),
+
+    /// The requested slot was not found.
+    ///
+    /// This probably means that the Spine document contains an error.
+    SlotNotFound(&'a str),
+
+    /// The requested attachment was not found.
+    ///
+    /// This probably means that the Spine document contains an error.
+    AttachmentNotFound(&'a str),
+
+    /// The attachment of `attachment_overrides` was not found in the skin that
+    ///  `attachment_override_skins` requested for its slot.
+    OverrideAttachmentNotFound {
+        /// Name of the skin.
+        skin: &'a str,
+
+        /// Name of the slot.
+        slot: &'a str,
+
+        /// Name of the attachment.
+        attachment: String,
+    },
+
+    /// The curve function was not recognized.
+    UnknownCurveFunction(String),
+
+    /// The draw order timeline moves a slot outside of the list of slots.
+    ///
+    /// This probably means that the Spine document contains an error.
+    InvalidDrawOrder(&'a str),
+
+    /// A color is not in the `RRGGBBAA` or `RRGGBB` hexadecimal format.
+    ///
+    /// This probably means that the Spine document contains an error.
+    InvalidColor(&'a str),
+
+    /// The range of the `CalculationOptions` is empty once clamped to the duration of the
+    ///  animation, their loop start is outside of it, or their duration isn't strictly
+    ///  positive.
+    InvalidRange,
+
+    /// The delay between the frames isn't strictly positive and finite, or there are more than
+    ///  `MAX_FRAMES` frames.
+    InvalidFrameRate,
+}
+
+impl<'a> CalculationError<'a> {
+    /// Returns true if the error comes from the content of the Spine document, and false if it
+    ///  comes from the parameters that were passed (ie. an unknown skin or animation name, an
+    ///  overridden attachment that isn't in its skin, or an invalid range).
+    pub fn is_document_error(&self) -> bool {
+        match self {
+            &CalculationError::SkinNotFound => false,
+            &CalculationError::AnimationNotFound => false,
+            &CalculationError::OverrideAttachmentNotFound { .. } => false,
+            &CalculationError::InvalidRange => false,
+            &CalculationError::InvalidFrameRate => false,
+            _ => true,
+        }
+    }
+}
+
+/// An animation applied over the lower ones by `calculate_layers`.
+struct Layer<'a> {
+    /// Name of the animation.
+    animation: &'a str,
+
+    /// Time in the animation, which must already be looped.
+    elapsed: f32,
+
+    /// Animation that is being crossfaded out, the time in this animation, and the weight of
+    ///  `animation` between 0 and 1.
+    mix: Option<(&'a str, f32, f32)>,
+
+    /// Weight of the layer over the lower ones, between 0 and 1.
+    alpha: f32,
+}
+
+/// Informations about a bone's position.
+///
+/// Can be absolute or relative to its parent.
+#[derive(Debug, Clone)]
+struct BoneData {
+    position: (f32, f32),
+    rotation: f32,
+    scale: (f32, f32),
+}
+
+impl BoneData {
+    fn to_matrix(&self) -> Matrix4<f32> {
+        use cgmath::{Matrix2, Vector3};
+
+        let scale_matrix = Matrix4::new(self.scale.0, 0.0, 0.0, 0.0, 0.0, self.scale.1, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0);
+        let rotation_matrix = Matrix4::from(Matrix2::from_angle(cgmath::deg(self.rotation).into()));
+        let translation_matrix = Matrix4::from_translation(&Vector3::new(self.position.0, self.position.1, 0.0));
+
+        translation_matrix * rotation_matrix * scale_matrix
+    }
+
+    /// Interpolates between `self` and `other`. A `weight` of 0 returns `self` and a `weight`
+    ///  of 1 returns `other`. Rotations go the shortest way around.
+    fn mix(&self, other: &BoneData, weight: f32) -> BoneData {
+        let mut rotation = (other.rotation - self.rotation) % 360.0;
+        if rotation > 180.0 { rotation -= 360.0; }
+        if rotation < -180.0 { rotation += 360.0; }
+
+        BoneData {
+            position: (self.position.0 + (other.position.0 - self.position.0) * weight,
+                       self.position.1 + (other.position.1 - self.position.1) * weight),
+            rotation: self.rotation + rotation * weight,
+            scale: (self.scale.0 + (other.scale.0 - self.scale.0) * weight,
+                    self.scale.1 + (other.scale.1 - self.scale.1) * weight),
+        }
+    }
+}
+
+impl std::ops::Add<BoneData> for BoneData {
+    type Output = BoneData;
+
+    fn add(self, rhs: BoneData) -> BoneData {
+        BoneData {
+            position: (self.position.0 + rhs.position.0, self.position.1 + rhs.position.1),
+            rotation: self.rotation + rhs.rotation,
+            scale: (self.scale.0 * rhs.scale.0, self.scale.1 * rhs.scale.1),
+        }
+    }
+}
+
+/// Reads the whole content of a reader and parses it as JSON.
+///
+/// The content is read before being parsed so that the errors of the reader are not mistaken
+///  for invalid JSON.
+fn read_json<R: Read>(reader: &mut R) -> Result<from_json::Json, LoadError> {
+    let mut data = Vec::new();
+    try!(reader.read_to_end(&mut data)
+        .map_err(|e| LoadError::Io(e.kind(), format!("{}", e))));
+
+    from_json::Json::from_reader(&mut &data[..])
+        .map_err(|e| LoadError::Json(format!("{:?}", e)))
+}
+
+/// Replaces the parts of the relative transformation of the bones overridden by the user.
+fn apply_bone_overrides(bones: &mut [(&format::Bone, BoneData)], options: &CalculationOptions) {
+    for &mut (bone, ref mut data) in bones.iter_mut() {
+        if let Some(bone_override) = options.bone_overrides.get(&bone.name) {
+            if let Some(position) = bone_override.position { data.position = position; }
+            if let Some(rotation) = bone_override.rotation { data.rotation = rotation; }
+            if let Some(scale) = bone_override.scale { data.scale = scale; }
+        }
+    }
+}
+
+/// Returns the time of the last keyframe of the timelines of a bone.
+fn get_bone_timeline_end(timeline: &format::BoneTimeline) -> f64 {
+    let mut end = 0.0f64;
+    if let Some(ref translate) = timeline.translate {
+        end = translate.iter().fold(end, |end, k| end.max(k.time));
+    }
+    if let Some(ref rotate) = timeline.rotate {
+        end = rotate.iter().fold(end, |end, k| end.max(k.time));
+    }
+    if let Some(ref scale) = timeline.scale {
+        end = scale.iter().fold(end, |end, k| end.max(k.time));
+    }
+    end
+}
+
+/// Returns the time of the last keyframe of the timelines of a slot.
+fn get_slot_timeline_end(timeline: &format::SlotTimeline) -> f64 {
+    let mut end = 0.0f64;
+    if let Some(ref attachment) = timeline.attachment {
+        end = attachment.iter().fold(end, |end, k| end.max(k.time));
+    }
+    if let Some(ref color) = timeline.color {
+        end = color.iter().fold(end, |end, k| end.max(k.time.unwrap_or(0.0)));
+    }
+    if let Some(ref rgb) = timeline.rgb {
+        end = rgb.iter().fold(end, |end, k| end.max(k.time.unwrap_or(0.0)));
+    }
+    if let Some(ref alpha) = timeline.alpha {
+        end = alpha.iter().fold(end, |end, k| end.max(k.time.unwrap_or(0.0)));
+    }
+    end
+}
+
+/// Returns the time at which the timelines of a bone or a slot must be sampled, which is
+///  modulo'd by `end` if they are in the `looping_timelines` of the options.
+fn get_timeline_elapsed(name: &str, end: f64, elapsed: f32, options: &CalculationOptions)
+    -> f32
+{
+    if end > 0.0 && options.looping_timelines.contains(name) {
+        elapsed % end as f32
+    } else {
+        elapsed
+    }
+}
+
+/// Builds an animation without any timeline.
+fn empty_animation() -> format::Animation {
+    format::Animation {
+        bones: None,
+        slots: None,
+        attachments: None,
+        events: None,
+        draworder: None,
+    }
+}
+
+/// Returns the number of frames from the beginning to the end of an animation, both included,
+///  given the number of delays between two frames that fit in the animation.
+fn get_frame_count(delays: f32) -> Result<usize, CalculationError<'static>> {
+    // also rejects NaN
+    if !(delays < MAX_FRAMES as f32) {
+        return Err(CalculationError::InvalidFrameRate);
+    }
+
+    Ok(delays.floor() as usize + 1)
+}
+
+/// Loops `elapsed` over the duration of an animation, or over the range of the options if
+///  there is one, starting the next loops at the loop start of the options.
+fn loop_time(duration: f32, elapsed: f32, options: &CalculationOptions)
+    -> Result<f32, CalculationError<'static>>
+{
+    let elapsed = match options.duration {
+        Some(target) if target > 0.0 => elapsed * duration / target,
+        Some(_) => return Err(CalculationError::InvalidRange),
+        None => elapsed
+    };
+
+    let (start, end) = match options.range {
+        Some((start, end)) => (start.max(0.0).min(duration), end.max(0.0).min(duration)),
+        None if options.loop_start.is_none() => return Ok(elapsed % duration),
+        None => (0.0, duration)
+    };
+
+    if start >= end {
+        return Err(CalculationError::InvalidRange);
+    }
+
+    match options.loop_start {
+        Some(loop_start) => {
+            if loop_start < start || loop_start >= end {
+                return Err(CalculationError::InvalidRange);
+            }
+
+            let time = start + elapsed;
+            if time < end {
+                Ok(time)
+            } else {
+                Ok(loop_start + (time - end) % (end - loop_start))
+            }
+        },
+        None => Ok(start + elapsed % (end - start))
+    }
+}
+
+/// Returns the time of a frame among `count` frames evenly spaced over `duration`, the last one
+///  being exactly at `duration`.
+fn get_frame_time(duration: f32, index: usize, count: usize) -> f32 {
+    if count == 1 {
+        0.0
+    } else if index == count - 1 {
+        duration
+    } else {
+        duration * index as f32 / (count - 1) as f32
+    }
+}
+
+/// Applies the root transform of the options, if any, to the world matrix of the bones.
+fn apply_root_transform(bones: &mut [(&str, Matrix4<f32>)], options: &CalculationOptions) {
+    if let Some(root) = options.root_transform {
+        for &mut (_, ref mut matrix) in bones.iter_mut() {
+            *matrix = root * *matrix;
+        }
+    }
+}
+
+/// Returns the setup pose of a bone relative to its parent.
+fn get_bone_default_local_setup(bone: &format::Bone) -> BoneData {
+    BoneData {
+        position: (bone.x.unwrap_or(0.0) as f32, bone.y.unwrap_or(0.0) as f32),
+        rotation: bone.rotation.unwrap_or(0.0) as f32,
+        scale: (bone.scaleX.unwrap_or(1.0) as f32, bone.scaleY.unwrap_or(1.0) as f32),
+    }
+}
+
+/// Returns true if a number is missing or finite.
+fn is_finite(value: Option<f64>) -> bool {
+    value.map(|v| v.is_finite()).unwrap_or(true)
+}
+
+/// Returns true if the points of a curve are finite.
+fn is_curve_finite(curve: &Option<format::TimelineCurve>) -> bool {
+    match curve {
+        &Some(format::TimelineCurve::CurveBezier(ref points)) =>
+            points.iter().all(|p| p.is_finite()),
+        _ => true
+    }
+}
+
+/// Checks that all the numbers of a document are finite.
+fn check_numbers(document: &format::Document) -> Result<(), LoadError> {
+    if let Some(ref bones) = document.bones {
+        for bone in bones.iter() {
+            if ![bone.length, bone.x, bone.y, bone.scaleX, bone.scaleY, bone.rotation].iter()
+                .all(|&v| is_finite(v))
+            {
+                return Err(LoadError::InvalidNumber(format!("bone `{}`", bone.name)));
+            }
+        }
+    }
+
+    if let Some(ref skins) = document.skins {
+        for (skin_name, skin) in skins.iter() {
+            for attachments in skin.values() {
+                for (name, a) in attachments.iter() {
+                    if ![a.x, a.y, a.scaleX, a.scaleY, a.rotation, a.width, a.height, a.fps].iter()
+                        .all(|&v| is_finite(v)) ||
+                       !a.vertices.iter().chain(a.lengths.iter()).chain(a.uvs.iter())
+                        .flat_map(|v| v.iter()).all(|v| v.is_finite()) ||
+                       a.sequence.as_ref().map(|s| s.count > MAX_SEQUENCE_COUNT).unwrap_or(false)
+                    {
+                        return Err(LoadError::InvalidNumber(format!("attachment `{}` of skin `{}`",
+                                                                    name, skin_name)));
+                    }
                 }
+            }
+        }
+    }
 
-                result
+    if let Some(ref events) = document.events {
+        for (name, event) in events.iter() {
+            if ![event.float_, event.volume, event.balance].iter().all(|&v| is_finite(v)) {
+                return Err(LoadError::InvalidNumber(format!("event `{}`", name)));
+            }
+        }
+    }
+
+    if let Some(ref animations) = document.animations {
+        for (anim_name, animation) in animations.iter() {
+            try!(check_animation_numbers(anim_name, animation));
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that all the numbers of an animation are finite.
+fn check_animation_numbers(anim_name: &str, animation: &format::Animation)
+    -> Result<(), LoadError>
+{
+    let error = || LoadError::InvalidNumber(format!("animation `{}`", anim_name));
+
+    if let Some(ref bones) = animation.bones {
+        for timelines in bones.values() {
+            if let Some(ref translate) = timelines.translate {
+                if !translate.iter().all(|e| e.time.is_finite() && is_finite(e.x) &&
+                                              is_finite(e.y) && is_curve_finite(&e.curve))
+                {
+                    return Err(error());
+                }
+            }
+            if let Some(ref rotate) = timelines.rotate {
+                if !rotate.iter().all(|e| e.time.is_finite() && is_finite(e.angle) &&
+                                           is_curve_finite(&e.curve))
+                {
+                    return Err(error());
+                }
+            }
+            if let Some(ref scale) = timelines.scale {
+                if !scale.iter().all(|e| e.time.is_finite() && is_finite(e.x) &&
+                                          is_finite(e.y) && is_curve_finite(&e.curve))
+                {
+                    return Err(error());
+                }
+            }
+        }
+    }
+
+    if let Some(ref slots) = animation.slots {
+        for timelines in slots.values() {
+            if let Some(ref attachment) = timelines.attachment {
+                if !attachment.iter().all(|e| e.time.is_finite()) {
+                    return Err(error());
+                }
+            }
+            if let Some(ref color) = timelines.color {
+                if !color.iter().all(|e| is_finite(e.time) && is_curve_finite(&e.curve)) {
+                    return Err(error());
+                }
+            }
+            if let Some(ref rgb) = timelines.rgb {
+                if !rgb.iter().all(|e| is_finite(e.time) && is_curve_finite(&e.curve)) {
+                    return Err(error());
+                }
+            }
+            if let Some(ref alpha) = timelines.alpha {
+                if !alpha.iter().all(|e| is_finite(e.time) && is_finite(e.value) &&
+                                         is_curve_finite(&e.curve))
+                {
+                    return Err(error());
+                }
+            }
+        }
+    }
+
+    for timelines in get_attachments_timelines(animation) {
+        if let Some(ref sequence) = timelines.sequence {
+            if !sequence.iter().all(|e| e.time.is_finite() && is_finite(e.delay) &&
+                                        e.delay.map(|d| d > 0.0).unwrap_or(true))
+            {
+                return Err(error());
+            }
+        }
+    }
+
+    if let Some(ref events) = animation.events {
+        if !events.iter().all(|e| e.time.is_finite() && is_finite(e.float_) &&
+                                  is_finite(e.volume) && is_finite(e.balance))
+        {
+            return Err(error());
+        }
+    }
+
+    if let Some(ref draworder) = animation.draworder {
+        if !draworder.iter().all(|e| e.time.is_finite()) {
+            return Err(error());
+        }
+    }
+
+    Ok(())
+}
+
+/// Copies the geometry of their source mesh into the linked meshes.
+///
+/// A linked mesh only contains the name of its source mesh, which is an attachment of the same
+///  slot in the skin given by its `skin` member, or in the default skin.
+fn resolve_linked_meshes(document: &mut format::Document) -> Result<(), LoadError> {
+    // the skin, slot and name of each linked mesh, and a copy of its source
+    let mut resolved = Vec::new();
+
+    if let Some(ref skins) = document.skins {
+        for (skin_name, skin) in skins.iter() {
+            for (slot_name, attachments) in skin.iter() {
+                for (name, attachment) in attachments.iter() {
+                    if attachment.type_ != Some(format::AttachmentType::LinkedMesh) {
+                        continue;
+                    }
+
+                    let parent = try!(attachment.parent.as_ref().ok_or_else(|| {
+                        LoadError::Format(format!("linked mesh `{}` has no parent", name))
+                    }));
+
+                    let source_skin = attachment.skin.as_ref().map(|s| &s[..])
+                                                .unwrap_or("default");
+                    let source = try!(skins.get(source_skin).and_then(|s| s.get(slot_name))
+                                           .and_then(|a| a.get(parent))
+                                           .ok_or_else(|| LoadError::AttachmentNotFound(
+                                               parent.clone())));
+
+                    resolved.push((skin_name.clone(), slot_name.clone(), name.clone(),
+                                   source.clone()));
+                }
+            }
+        }
+    }
+
+    if let Some(ref mut skins) = document.skins {
+        for (skin_name, slot_name, name, source) in resolved.into_iter() {
+            let attachment = skins.get_mut(&skin_name).unwrap().get_mut(&slot_name).unwrap()
+                                  .get_mut(&name).unwrap();
+
+            attachment.vertices = source.vertices;
+            attachment.uvs = source.uvs;
+            attachment.triangles = source.triangles;
+            attachment.hull = source.hull;
+            if attachment.width.is_none() { attachment.width = source.width; }
+            if attachment.height.is_none() { attachment.height = source.height; }
+        }
+    }
+
+    Ok(())
+}
+
+/// Converts the curves of the `rgba`, `rgb` and `alpha` timelines of Spine 4 to the format of
+///  the other curves.
+///
+/// In Spine 4, the bezier curve of a keyframe contains 4 numbers per channel: the absolute time
+///  and value of its two control points. They are made relative to the keyframe and to the next
+///  one, keeping 4 numbers per channel, so that each channel can be interpolated separately.
+///  If `relative` is false, the curves are converted back to the format of Spine 4 instead.
+fn convert_channel_curves(anim_name: &str, animation: &mut format::Animation, relative: bool)
+    -> Result<(), LoadError>
+{
+    if let Some(ref mut slots) = animation.slots {
+        for (slot_name, timelines) in slots.iter_mut() {
+            let timeline_name = |kind: &str| {
+                format!("the {} timeline of slot `{}` in animation `{}`", kind, slot_name,
+                        anim_name)
+            };
+
+            if let Some(ref mut rgba) = timelines.rgba {
+                try!(convert_color_curves(rgba, 4, &timeline_name("rgba"), relative));
+            }
+            if let Some(ref mut rgb) = timelines.rgb {
+                try!(convert_color_curves(rgb, 3, &timeline_name("rgb"), relative));
+            }
+            if let Some(ref mut alpha) = timelines.alpha {
+                let times: Vec<f64> = alpha.iter().map(|k| k.time.unwrap_or(0.0)).collect();
+                let values: Vec<Vec<f64>> = alpha.iter().map(|k| vec![k.value.unwrap_or(0.0)])
+                                                 .collect();
+                let curves = alpha.iter_mut().map(|k| &mut k.curve).collect();
+                try!(convert_curves(&times, &values, curves, &timeline_name("alpha"), relative));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Converts the curves of a Spine 4 color timeline whose first `channels` channels, in the
+///  red, green, blue and alpha order, are animated.
+fn convert_color_curves(timeline: &mut [format::SlotColorTimeline], channels: usize,
+    timeline_name: &str, relative: bool) -> Result<(), LoadError>
+{
+    let times: Vec<f64> = timeline.iter().map(|k| k.time.unwrap_or(0.0)).collect();
+
+    let mut values = Vec::with_capacity(timeline.len());
+    for keyframe in timeline.iter() {
+        let color = try!(parse_optional_color(&keyframe.color).map_err(|_| {
+            LoadError::Format(format!("invalid color in {}", timeline_name))
+        }));
+        let channel = |c: u8| c as f64 / 255.0;
+        let color = [channel(color.c.r), channel(color.c.g), channel(color.c.b),
+                     channel(color.a)];
+        values.push(color[.. channels].to_vec());
+    }
+
+    let curves = timeline.iter_mut().map(|k| &mut k.curve).collect();
+    convert_curves(&times, &values, curves, timeline_name, relative)
+}
+
+/// Makes the control points of Spine 4 curves relative to the time and to the value of the
+///  channels of their keyframe and of the next one, or absolute again if `relative` is false.
+fn convert_curves(times: &[f64], values: &[Vec<f64>],
+    curves: Vec<&mut Option<format::TimelineCurve>>, timeline_name: &str, relative: bool)
+    -> Result<(), LoadError>
+{
+    // the curve of the last keyframe is never used
+    for (index, curve) in curves.into_iter().enumerate().take(times.len().saturating_sub(1)) {
+        let points = match curve {
+            &mut Some(format::TimelineCurve::CurveBezier(ref mut points)) => points,
+            _ => continue
+        };
+
+        let (from, to) = (&values[index], &values[index + 1]);
+        if points.len() != from.len() * 4 {
+            return Err(LoadError::Format(format!("expected {} numbers per curve in {}",
+                                                 from.len() * 4, timeline_name)));
+        }
+
+        let duration = times[index + 1] - times[index];
+        let relative_time = |time: f64| {
+            if duration > 0.0 { (time - times[index]) / duration } else { 0.0 }
+        };
+
+        for (channel, (&from, &to)) in from.iter().zip(to.iter()).enumerate() {
+            let point = &mut points[channel * 4 .. channel * 4 + 4];
+
+            if !relative {
+                point[0] = times[index] + point[0] * duration;
+                point[2] = times[index] + point[2] * duration;
+                point[1] = from + point[1] * (to - from);
+                point[3] = from + point[3] * (to - from);
+                continue;
+            }
+
+            point[0] = relative_time(point[0]);
+            point[2] = relative_time(point[2]);
+
+            // a channel that doesn't change is interpolated linearly
+            if to != from {
+                point[1] = (point[1] - from) / (to - from);
+                point[3] = (point[3] - from) / (to - from);
+            } else {
+                point[1] = point[0];
+                point[3] = point[2];
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Moves the `rgba` timelines of the slots, which is how Spine 4 names the color timelines, to
+///  their `color` member.
+fn merge_rgba_timelines(animation: &mut format::Animation) {
+    if let Some(ref mut slots) = animation.slots {
+        for (_, timelines) in slots.iter_mut() {
+            if let Some(rgba) = timelines.rgba.take() {
+                if timelines.color.is_none() {
+                    timelines.color = Some(rgba);
+                }
+            }
+        }
+    }
+}
+
+/// Multiplies the positions and lengths of a document by `scale`.
+fn scale_document(document: &mut format::Document, scale: f64) {
+    if let Some(ref mut bones) = document.bones {
+        for bone in bones.iter_mut() {
+            bone.x = bone.x.map(|v| v * scale);
+            bone.y = bone.y.map(|v| v * scale);
+            bone.length = bone.length.map(|v| v * scale);
+        }
+    }
 
-            } else {
-                Vec::new()
-            };
+    if let Some(ref mut skins) = document.skins {
+        for (_, skin) in skins.iter_mut() {
+            for (_, attachments) in skin.iter_mut() {
+                for (_, attachment) in attachments.iter_mut() {
+                    attachment.x = attachment.x.map(|v| v * scale);
+                    attachment.y = attachment.y.map(|v| v * scale);
+                    attachment.width = attachment.width.map(|v| v * scale);
+                    attachment.height = attachment.height.map(|v| v * scale);
 
-        // if we are animating, replacing the values by the ones overridden by the animation
-        if let Some(animation) = animation {
-            if let Some(anim_slots) = animation.slots.as_ref() {
-                for (slot_name, timelines) in anim_slots.iter() {
-                    // calculating the variation from the animation
-                    let (anim_color, anim_attach) =
-                        try!(timelines_to_slotdata(timelines, elapsed));
-
-                    // adding this to the `slots` vec above
-                    match slots.iter_mut().find(|&&mut (s, _, _, _)| s == slot_name) {
-                        Some(&mut (_, _, ref mut color, ref mut attachment)) => {
-                            if let Some(c) = anim_color { *color = Some(c) };
-                            if let Some(a) = anim_attach { *attachment = Some(a) };
-                        },
-                        None => ()
+                    // meshes don't have a vertex count, but have a texture coordinate per
+                    // vertex
+                    let count = match (attachment.vertexCount, attachment.uvs.as_ref()) {
+                        (Some(count), _) => Some(cmp::max(count, 0) as usize),
+                        (None, Some(uvs)) => Some(uvs.len() / 2),
+                        (None, None) => None
                     };
+                    if let Some(ref mut vertices) = attachment.vertices {
+                        let count = count.unwrap_or(vertices.len() / 2);
+                        scale_vertices(vertices, count, scale);
+                    }
+
+                    if let Some(ref mut lengths) = attachment.lengths {
+                        for l in lengths.iter_mut() { *l = *l * scale; }
+                    }
                 }
             }
-        };
+        }
+    }
 
-        // now finding the attachment of each slot
-        let slots = {
-            let mut results = Vec::new();
+    if let Some(ref mut animations) = document.animations {
+        for (_, animation) in animations.iter_mut() {
+            scale_animation(animation, scale);
+        }
+    }
+}
 
-            for (slot_name, bone_data, _color, attachment) in slots.into_iter() {
-                if let Some(attachment) = attachment {
-                    let attachments = match skin.iter().chain(default_skin.iter())
-                                                .find(|&(slot, _)| slot == slot_name)
-                    {
-                        Some(a) => a,
-                        None => continue
-                    };
+/// Multiplies the positions of `count` vertices by `scale`.
+///
+/// Weighted vertices contain, for each vertex, the number of bones followed by the index, the
+///  position and the weight of each bone. Only the positions are scaled.
+fn scale_vertices(vertices: &mut [f64], count: usize, scale: f64) {
+    if vertices.len() == count * 2 {
+        for v in vertices.iter_mut() { *v = *v * scale; }
+        return;
+    }
 
-                    let attachment = try!(attachments.1.iter()
-                        .find(|&(a, _)| a == attachment)
-                        .ok_or(CalculationError::AttachmentNotFound(attachment)));
+    let mut index = 0;
+    for _ in 0 .. count {
+        let bones = match vertices.get(index) {
+            Some(&bones) if bones >= 0.0 => bones as usize,
+            _ => return
+        };
+        index += 1;
 
-                    let attachment_transform = get_attachment_transformation(attachment.1);
-                    let bone_data = bone_data * attachment_transform;
+        for _ in 0 .. bones {
+            if index + 3 >= vertices.len() {
+                return;
+            }
 
-                    let attachment = if let Some(ref name) = attachment.1.name {
-                        &name[..]
-                    } else {
-                        &attachment.0[..]
-                    };
+            vertices[index + 1] = vertices[index + 1] * scale;
+            vertices[index + 2] = vertices[index + 2] * scale;
+            index += 4;
+        }
+    }
+}
 
-                    results.push((
-                        attachment,
-                        bone_data,
-                        Rgba { a: 255, c: Rgb::new(255, 255, 255) }
-                    ));
+/// Multiplies the translations of an animation by `scale`.
+fn scale_animation(animation: &mut format::Animation, scale: f64) {
+    if let Some(ref mut bones) = animation.bones {
+        for (_, timelines) in bones.iter_mut() {
+            if let Some(ref mut translate) = timelines.translate {
+                for keyframe in translate.iter_mut() {
+                    keyframe.x = keyframe.x.map(|v| v * scale);
+                    keyframe.y = keyframe.y.map(|v| v * scale);
                 }
             }
+        }
+    }
+}
 
-            results
-        };
-
-        // final result
-        Ok(Calculation {
-            sprites: slots
-        })
+/// Returns the name of the sprite drawn by an attachment.
+///
+/// This is the `path` of the attachment, or its `name`, or the key of the attachment in the skin.
+fn get_sprite_name<'a>(key: &'a str, attachment: &'a format::Attachment) -> &'a str {
+    if let Some(ref path) = attachment.path {
+        &path[..]
+    } else if let Some(ref name) = attachment.name {
+        &name[..]
+    } else {
+        key
     }
 }
 
-/// Result of an animation state calculation.
-#[derive(Debug)]
-pub struct Calculation<'a> {
-    /// The list of sprites that should be drawn.
-    ///
-    /// The elements are sorted from bottom to top, ie. each element can cover the previous one.
-    ///
-    /// The matrix assumes that the sprite is displayed from (-1, -1) to (1, 1), ie. would cover
-    ///  the whole screen.
-    pub sprites: Vec<(&'a str, Matrix4<f32>, Rgba<u8>)>,
+/// Returns the timelines of all the attachments of an animation, in all skins and slots.
+fn get_attachments_timelines(animation: &format::Animation) -> Vec<&format::AttachmentTimeline> {
+    match animation.attachments {
+        Some(ref skins) => skins.values().flat_map(|slots| slots.values())
+                                .flat_map(|attachments| attachments.values())
+                                .collect(),
+        None => Vec::new()
+    }
 }
 
-/// Error that can happen while calculating an animation.
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub enum CalculationError<'a> {
-    /// The requested skin was not found.
-    SkinNotFound,
+/// Builds the names of the regions of all the attachments that have a sequence.
+///
+/// The name of a region is the name of the sprite followed by its zero-padded number.
+fn get_sequences_regions(document: &format::Document)
+    -> HashMap<String, HashMap<String, HashMap<String, Vec<String>>>>
+{
+    let mut result = HashMap::new();
 
-    /// The requested animation was not found.
-    AnimationNotFound,
+    if let Some(ref skins) = document.skins {
+        for (skin_name, skin) in skins.iter() {
+            for (slot_name, slot) in skin.iter() {
+                for (name, attachment) in slot.iter() {
+                    let sequence = match attachment.sequence {
+                        Some(ref s) => s,
+                        None => continue
+                    };
 
-    /// The requested bone was not found in the list of bones.
-    ///
-    /// This probably means that the Spine document contains an error.
-    BoneNotFound(&'a str),
+                    let sprite = get_sprite_name(name, attachment);
+                    let start = sequence.start.unwrap_or(1);
+                    let digits = cmp::max(sequence.digits.unwrap_or(0), 0) as usize;
 
-    /// The requested slot was not found.
-    ///
-    /// This probably means that the Spine document contains an error.
-    SlotNotFound(&'a str),
+                    let regions = (0 .. cmp::max(sequence.count, 0))
+                        .map(|i| format!("{}{:0width$}", sprite, start as i64 + i as i64,
+                                         width = digits))
+                        .collect();
 
-    /// The requested attachment was not found.
-    ///
-    /// This probably means that the Spine document contains an error.
-    AttachmentNotFound(&'a str),
+                    result.entry(skin_name.clone()).or_insert_with(HashMap::new)
+                          .entry(slot_name.clone()).or_insert_with(HashMap::new)
+                          .insert(name.clone(), regions);
+                }
+            }
+        }
+    }
 
-    /// The curve function was not recognized.
-    UnknownCurveFunction(String),
+    result
 }
 
-/// Informations about a bone's position.
+/// Returns the index of the region of a sequence that must be displayed.
 ///
-/// Can be absolute or relative to its parent.
-#[derive(Debug, Clone)]
-struct BoneData {
-    position: (f32, f32),
-    rotation: f32,
-    scale: (f32, f32),
-}
+/// The "random" mode picks a new region after each delay. The choice only depends on the
+///  seed and on the number of delays elapsed since the keyframe, so that the same seed always
+///  gives the same regions.
+fn get_sequence_index(sequence: &format::Sequence,
+    timeline: Option<&Vec<format::SequenceKeyframe>>, elapsed: f32, seed: u64) -> usize
+{
+    let setup = cmp::max(sequence.setup.unwrap_or(0), 0) as usize;
 
-impl BoneData {
-    fn to_matrix(&self) -> Matrix4<f32> {
-        use cgmath::{Matrix2, Vector3};
+    // finding the last keyframe before `elapsed`
+    let keyframe = match timeline.and_then(|t| t.iter().rev()
+                                              .find(|k| elapsed >= k.time as f32))
+    {
+        Some(k) => k,
+        None => return setup
+    };
 
-        let scale_matrix = Matrix4::new(self.scale.0, 0.0, 0.0, 0.0, 0.0, self.scale.1, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0);
-        let rotation_matrix = Matrix4::from(Matrix2::from_angle(cgmath::deg(self.rotation).into()));
-        let translation_matrix = Matrix4::from_translation(&Vector3::new(self.position.0, self.position.1, 0.0));
+    // the calculations are done with i64 so that a tiny delay can't overflow them
+    let count = cmp::max(sequence.count, 0) as i64;
+    let mut index = cmp::max(keyframe.index.unwrap_or(0), 0) as i64;
+    let delay = keyframe.delay.unwrap_or(0.0);
+    let mode = keyframe.mode.unwrap_or(format::SequenceMode::Hold);
 
-        translation_matrix * rotation_matrix * scale_matrix
+    if mode != format::SequenceMode::Hold && delay > 0.0 {
+        let steps = (elapsed as f64 - keyframe.time) / delay + 0.0001;
+        index += steps.max(0.0).min(1.0e15) as i64;
     }
-}
 
-impl std::ops::Add<BoneData> for BoneData {
-    type Output = BoneData;
+    let pingpong = cmp::max(count * 2 - 2, 0);
+    let index = match mode {
+        format::SequenceMode::Hold => index,
+        format::SequenceMode::Once => cmp::min(index, count - 1),
+        format::SequenceMode::Loop => index % cmp::max(count, 1),
+        format::SequenceMode::PingPong => {
+            let index = if pingpong == 0 { 0 } else { index % pingpong };
+            if index >= count { pingpong - index } else { index }
+        },
+        format::SequenceMode::OnceReverse => cmp::max(count - 1 - index, 0),
+        format::SequenceMode::LoopReverse => count - 1 - index % cmp::max(count, 1),
+        format::SequenceMode::PingPongReverse => {
+            let index = if pingpong == 0 { 0 } else { (index + count - 1) % pingpong };
+            if index >= count { pingpong - index } else { index }
+        },
+        format::SequenceMode::Random => {
+            if count == 0 { 0 } else { (random(seed, index as u64) % count as u64) as i64 }
+        },
+    };
 
-    fn add(self, rhs: BoneData) -> BoneData {
-        BoneData {
-            position: (self.position.0 + rhs.position.0, self.position.1 + rhs.position.1),
-            rotation: self.rotation + rhs.rotation,
-            scale: (self.scale.0 * rhs.scale.0, self.scale.1 * rhs.scale.1),
-        }
-    }
+    cmp::max(index, 0) as usize
 }
 
-/// Returns the setup pose of a bone relative to its parent.
-fn get_bone_default_local_setup(bone: &format::Bone) -> BoneData {
-    BoneData {
-        position: (bone.x.unwrap_or(0.0) as f32, bone.y.unwrap_or(0.0) as f32),
-        rotation: bone.rotation.unwrap_or(0.0) as f32,
-        scale: (bone.scaleX.unwrap_or(1.0) as f32, bone.scaleY.unwrap_or(1.0) as f32),
-    }
+/// Returns a pseudo-random number that only depends on `seed` and `step`.
+fn random(seed: u64, step: u64) -> u64 {
+    // splitmix64
+    let mut value = seed ^ step.wrapping_mul(0x9E3779B97F4A7C15);
+    value = (value ^ (value >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94D049BB133111EB);
+    value ^ (value >> 31)
 }
 
 /// Returns the `Matrix` of an attachment.
@@ -484,7 +3884,12 @@ fn get_attachment_transformation(attachment: &format::Attachment) -> Matrix4<f32
 }
 
 /// Builds the `Matrix4` corresponding to a timeline.
-fn timelines_to_bonedata(timeline: &format::BoneTimeline, elapsed: f32) -> Result<BoneData, CalculationError> {
+///
+/// Keyframes that share the same time form an empty interval that is never selected, which means
+///  that the value of the last of these keyframes is used, as if the curve was stepped.
+fn timelines_to_bonedata<'a>(timeline: &'a format::BoneTimeline, elapsed: f32,
+    options: &CalculationOptions) -> Result<BoneData, CalculationError<'a>>
+{
     // calculating the current position
     let position = if let Some(timeline) = timeline.translate.as_ref() {
         // finding in which interval we are
@@ -497,9 +3902,9 @@ fn timelines_to_bonedata(timeline: &format::BoneTimeline, elapsed: f32) -> Resul
 
                 (
                     try!(calculate_curve(&before.curve, before.x.unwrap_or(0.0) as f32,
-                        after.x.unwrap_or(0.0) as f32, position)),
+                        after.x.unwrap_or(0.0) as f32, position, options)),
                     try!(calculate_curve(&before.curve, before.y.unwrap_or(0.0) as f32,
-                        after.y.unwrap_or(0.0) as f32, position))
+                        after.y.unwrap_or(0.0) as f32, position, options))
                 )
             },
             None => {
@@ -526,7 +3931,7 @@ fn timelines_to_bonedata(timeline: &format::BoneTimeline, elapsed: f32) -> Resul
                 let position = (elapsed - (before.time as f32)) / ((after.time - before.time) as f32);
 
                 try!(calculate_curve(&before.curve, before.angle.unwrap_or(0.0) as f32,
-                    after.angle.unwrap_or(0.0) as f32, position))
+                    after.angle.unwrap_or(0.0) as f32, position, options))
             },
             None => {
                 // we didn't find an interval, assuming we are past the end
@@ -553,9 +3958,9 @@ fn timelines_to_bonedata(timeline: &format::BoneTimeline, elapsed: f32) -> Resul
 
                 (
                     try!(calculate_curve(&before.curve, before.x.unwrap_or(1.0) as f32,
-                        after.x.unwrap_or(1.0) as f32, position)),
+                        after.x.unwrap_or(1.0) as f32, position, options)),
                     try!(calculate_curve(&before.curve, before.y.unwrap_or(1.0) as f32,
-                        after.y.unwrap_or(1.0) as f32, position))
+                        after.y.unwrap_or(1.0) as f32, position, options))
                 )
             },
             None => {
@@ -582,8 +3987,8 @@ fn timelines_to_bonedata(timeline: &format::BoneTimeline, elapsed: f32) -> Resul
 /// Calculates a curve using the value of a "curve" member.
 ///
 /// Position must be between 0 and 1
-fn calculate_curve(formula: &Option<format::TimelineCurve>, from: f32, to: f32,
-    position: f32) -> Result<f32, CalculationError>
+fn calculate_curve<'a>(formula: &'a Option<format::TimelineCurve>, from: f32, to: f32,
+    position: f32, options: &CalculationOptions) -> Result<f32, CalculationError<'a>>
 {
     assert!(position >= 0.0 && position <= 1.0);
 
@@ -594,89 +3999,374 @@ fn calculate_curve(formula: &Option<format::TimelineCurve>, from: f32, to: f32,
             return Ok(from + position * (to - from)),
         &Some(format::TimelineCurve::CurvePredefined(ref a)) if a == "stepped" =>
             return Ok(from),
+        &Some(format::TimelineCurve::CurvePredefined(ref a))
+            if options.curve_functions.contains_key(a) =>
+            return Ok(from + options.curve_functions[a](position) * (to - from)),
         &Some(format::TimelineCurve::CurveBezier(ref a)) => &a[..],
         a => return Err(CalculationError::UnknownCurveFunction(format!("{:?}", a))),
     };
     
-    let (cx1, cy1, cx2, cy2) = match (bezier.get(0), bezier.get(1),
-                                      bezier.get(2), bezier.get(3))
+    if bezier.len() < 4 {
+        return Err(CalculationError::UnknownCurveFunction(format!("{:?}", bezier)));
+    }
+
+    Ok(from + calculate_bezier(&bezier[.. 4], position) * (to - from))
+}
+
+/// Returns the factor, between 0 and 1 unless the curve overshoots, of a bezier curve whose
+///  relative control points are `[cx1, cy1, cx2, cy2]` at a position between 0 and 1.
+fn calculate_bezier(points: &[f64], position: f32) -> f32 {
+    let (cx1, cy1, cx2, cy2) = (points[0] as f32, points[1] as f32, points[2] as f32,
+                                points[3] as f32);
+
+    // the x coordinate of the curve is monotonic as long as the control points are between 0
+    // and 1, so the parameter of the curve at `position` can be found by bisection, which
+    // gives a continuous result
+    let (cx1, cx2) = (cx1.max(0.0).min(1.0), cx2.max(0.0).min(1.0));
+    let bezier = |c1: f32, c2: f32, t: f32| {
+        3.0 * c1 * t * (1.0 - t) * (1.0 - t) + 3.0 * c2 * t * t * (1.0 - t) + t * t * t
+    };
+
+    let (mut low, mut high) = (0.0f32, 1.0f32);
+    for _ in 0 .. 24 {
+        let middle = (low + high) / 2.0;
+        if bezier(cx1, cx2, middle) < position {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+
+    bezier(cy1, cy2, (low + high) / 2.0)
+}
+
+/// Returns the position of a time relative to the first and last keyframes of a timeline.
+fn get_sampling_position(first: f64, last: f64, elapsed: f32) -> SamplingPosition {
+    if elapsed < first as f32 {
+        SamplingPosition::BeforeFirstKeyframe
+    } else if elapsed > last as f32 {
+        SamplingPosition::AfterLastKeyframe
+    } else {
+        SamplingPosition::Interpolated
+    }
+}
+
+/// Returns the indices of the slots in the order in which they must be drawn.
+fn get_draw_order<'a>(slots: &'a [format::Slot],
+    timeline: Option<&'a Vec<format::DrawOrderTimeline>>, elapsed: f32)
+    -> Result<Vec<usize>, CalculationError<'a>>
+{
+    // finding the last keyframe before `elapsed`
+    let offsets = match timeline.and_then(|t| t.iter().rev().find(|k| elapsed >= k.time as f32))
+                                .and_then(|k| k.offsets.as_ref())
     {
-        (Some(&cx1), Some(&cy1), Some(&cx2), Some(&cy2)) =>
-            (cx1 as f32, cy1 as f32, cx2 as f32, cy2 as f32),
-        a =>
-            return Err(CalculationError::UnknownCurveFunction(format!("{:?}", a)))
+        Some(o) => o,
+        None => return Ok((0 .. slots.len()).collect())
     };
 
-    let factor = (0 ..).map(|v| v as f32 * 0.02)
-        .take_while(|v| *v <= 1.0)
-        .map(|t| {
-            let x = 3.0 * cx1 * t * (1.0 - t) * (1.0 - t)
-                + 3.0 * cx2 * t * t * (1.0 - t) + t * t * t;
-            let y = 3.0 * cy1 * t * (1.0 - t) * (1.0 - t)
-                + 3.0 * cy2 * t * t * (1.0 - t) + t * t * t;
+    // each slot is moved by its offset, and the other slots fill the remaining places in their
+    // original order
+    let mut draw_order: Vec<Option<usize>> = (0 .. slots.len()).map(|_| None).collect();
+    let mut unchanged = Vec::with_capacity(slots.len());
+    let mut original_index = 0;
 
-            (x, y)
-        })
-        .scan((0.0, 0.0), |previous, current| {
-            let result = Some((previous.clone(), current));
-            *previous = current;
-            result
-        })
-        .find(|&(previous, current)| {
-            position >= previous.0 && position < current.0
-        })
-        .map(|((_, val), _)| val)
-        .unwrap_or(1.0);
+    for offset in offsets.iter() {
+        let slot_index = try!(slots.iter().position(|s| s.name == offset.slot)
+            .ok_or(CalculationError::SlotNotFound(&offset.slot)));
+
+        while original_index < slot_index {
+            unchanged.push(original_index);
+            original_index += 1;
+        }
+
+        let target = original_index as i32 + offset.offset;
+        if target < 0 || target as usize >= slots.len() || original_index != slot_index ||
+           draw_order[target as usize].is_some()
+        {
+            return Err(CalculationError::InvalidDrawOrder(&offset.slot));
+        }
+
+        draw_order[target as usize] = Some(original_index);
+        original_index += 1;
+    }
+
+    while original_index < slots.len() {
+        unchanged.push(original_index);
+        original_index += 1;
+    }
 
-    Ok(from + factor * (to - from))
+    // there are exactly as many unchanged slots as empty places
+    let mut unchanged = unchanged.into_iter();
+    Ok(draw_order.into_iter().map(|slot| slot.unwrap_or_else(|| unchanged.next().unwrap()))
+                 .collect())
 }
 
 /// Builds the color and attachment corresponding to a slot timeline.
-fn timelines_to_slotdata(timeline: &format::SlotTimeline, elapsed: f32)
-    -> Result<(Option<&str>, Option<&str>), CalculationError>
+///
+/// The attachment is `None` if the timeline doesn't change it at this time, and `Some(None)` if
+///  the timeline explicitly hides the slot. The channels of the color that are not animated,
+///  for example the red, green and blue channels when only the alpha is keyed, are taken from
+///  `base_color`.
+///
+/// Past the last keyframe, the value of this keyframe is kept. Like in the official runtimes,
+///  a last keyframe without attachment keeps the slot hidden until the end of the animation
+///  instead of going back to the setup attachment.
+fn timelines_to_slotdata<'a>(timeline: &'a format::SlotTimeline, elapsed: f32,
+    base_color: Rgba<u8>, options: &CalculationOptions)
+    -> Result<(Option<Rgba<u8>>, Option<Option<&'a str>>), CalculationError<'a>>
 {
     // calculating the attachment
-    let attachment = if let Some(timeline) = timeline.attachment.as_ref() {
-        // finding in which interval we are
-        match timeline.iter().zip(timeline.iter().skip(1))
-            .find(|&(before, after)| elapsed >= before.time as f32 && elapsed < after.time as f32)
-        {
-            Some((ref before, _)) => {
-                before.name.as_ref().map(|e| &e[..])
-            },
-            None => {
+    let attachment = timeline.attachment.as_ref()
+                             .and_then(|timeline| attachment_at(timeline, elapsed));
+
+    // calculating the color, whose channels may be animated separately
+    let color = match timeline.color.as_ref() {
+        Some(timeline) => try!(color_at(timeline, elapsed, options)),
+        None => None
+    };
+    let rgb = match timeline.rgb.as_ref() {
+        Some(timeline) => try!(color_at(timeline, elapsed, options)),
+        None => None
+    };
+    let alpha = match timeline.alpha.as_ref() {
+        Some(timeline) => try!(alpha_at(timeline, elapsed, options)),
+        None => None
+    };
+
+    let color = if color.is_none() && rgb.is_none() && alpha.is_none() {
+        None
+    } else {
+        let mut result = color.unwrap_or(base_color);
+        if let Some(rgb) = rgb { result.c = rgb.c; }
+        if let Some(alpha) = alpha {
+            result.a = (alpha * 255.0).round().max(0.0).min(255.0) as u8;
+        }
+        Some(result)
+    };
+
+    // returning
+    Ok((color, attachment))
+}
+
+/// Returns the color of a color timeline at the given time, or `None` before its first
+///  keyframe.
+fn color_at<'a>(timeline: &'a [format::SlotColorTimeline], elapsed: f32,
+    options: &CalculationOptions) -> Result<Option<Rgba<u8>>, CalculationError<'a>>
+{
+    let time = |keyframe: &format::SlotColorTimeline| keyframe.time.unwrap_or(0.0) as f32;
+
+    // finding in which interval we are
+    match timeline.iter().zip(timeline.iter().skip(1))
+        .find(|&(before, after)| elapsed >= time(before) && elapsed < time(after))
+    {
+        Some((ref before, ref after)) => {
+            // calculating the value using the curve function
+            let position = (elapsed - time(before)) / (time(after) - time(before));
+
+            let from = try!(parse_optional_color(&before.color));
+            let to = try!(parse_optional_color(&after.color));
+            Ok(Some(try!(interpolate_color(&before.curve, from, to, position, options))))
+        },
+        None => {
+            match timeline.first() {
+                // we are before the first keyframe, the setup color is kept
+                Some(first) if elapsed < time(first) => Ok(None),
                 // we didn't find an interval, assuming we are past the end
-                timeline.last().and_then(|t| (t.name.as_ref().map(|e| &e[..])))
+                _ => match timeline.last() {
+                    Some(last) => Ok(Some(try!(parse_optional_color(&last.color)))),
+                    None => Ok(None)
+                }
             }
         }
+    }
+}
 
-    } else {
-        // we have no timeline
-        None
-    };
+/// Returns the alpha, between 0 and 1, of an alpha timeline at the given time, or `None`
+///  before its first keyframe.
+fn alpha_at<'a>(timeline: &'a [format::SlotAlphaTimeline], elapsed: f32,
+    options: &CalculationOptions) -> Result<Option<f32>, CalculationError<'a>>
+{
+    let time = |keyframe: &format::SlotAlphaTimeline| keyframe.time.unwrap_or(0.0) as f32;
 
+    // finding in which interval we are
+    match timeline.iter().zip(timeline.iter().skip(1))
+        .find(|&(before, after)| elapsed >= time(before) && elapsed < time(after))
+    {
+        Some((ref before, ref after)) => {
+            let position = (elapsed - time(before)) / (time(after) - time(before));
 
-    // calculating the color
-    let color = if let Some(timeline) = timeline.color.as_ref() {
-        // finding in which interval we are
-        match timeline.iter().zip(timeline.iter().skip(1))
-            .find(|&(before, after)| elapsed >= before.time as f32 && elapsed < after.time as f32)
-        {
-            Some((ref before, _)) => {
-                before.color.as_ref().map(|e| &e[..])
-            },
-            None => {
+            let from = before.value.unwrap_or(0.0) as f32;
+            let to = after.value.unwrap_or(0.0) as f32;
+            Ok(Some(try!(calculate_curve(&before.curve, from, to, position, options))))
+        },
+        None => {
+            match timeline.first() {
+                // we are before the first keyframe, the setup alpha is kept
+                Some(first) if elapsed < time(first) => Ok(None),
                 // we didn't find an interval, assuming we are past the end
-                timeline.last().and_then(|t| (t.color.as_ref().map(|e| &e[..])))
+                _ => Ok(timeline.last().map(|last| last.value.unwrap_or(0.0) as f32))
             }
         }
+    }
+}
 
-    } else {
-        // we have no timeline
-        None
+/// Returns the attachment of an attachment timeline at the given time.
+///
+/// Returns `None` before the first keyframe, in which case the setup attachment is kept, and
+///  `Some(None)` if the slot is hidden.
+fn attachment_at(timeline: &[format::SlotAttachmentTimeline], elapsed: f32)
+    -> Option<Option<&str>>
+{
+    // finding in which interval we are
+    match timeline.iter().zip(timeline.iter().skip(1))
+        .find(|&(before, after)| elapsed >= before.time as f32 && elapsed < after.time as f32)
+    {
+        Some((ref before, _)) => {
+            Some(before.name.as_ref().map(|e| &e[..]))
+        },
+        None => {
+            match timeline.first() {
+                // we are before the first keyframe, the setup attachment is kept
+                Some(first) if elapsed < first.time as f32 => None,
+                // we didn't find an interval, assuming we are past the end
+                _ => timeline.last().map(|t| t.name.as_ref().map(|e| &e[..]))
+            }
+        }
+    }
+}
+
+/// Parses a color in the `RRGGBBAA` or `RRGGBB` hexadecimal format.
+///
+/// The alpha component is 255 if it is missing.
+fn parse_color(color: &str) -> Result<Rgba<u8>, CalculationError> {
+    if (color.len() != 8 && color.len() != 6) || !color.chars().all(|c| c.is_digit(16)) {
+        return Err(CalculationError::InvalidColor(color));
+    }
+
+    let component = |n: usize| u8::from_str_radix(&color[n * 2 .. n * 2 + 2], 16).unwrap();
+
+    Ok(Rgba {
+        a: if color.len() == 8 { component(3) } else { 255 },
+        c: Rgb::new(component(0), component(1), component(2)),
+    })
+}
+
+/// Parses the color of a keyframe, which is white if it is missing.
+fn parse_optional_color(color: &Option<String>) -> Result<Rgba<u8>, CalculationError> {
+    match color {
+        &Some(ref c) => parse_color(c),
+        &None => Ok(Rgba { a: 255, c: Rgb::new(255, 255, 255) }),
+    }
+}
+
+/// Applies the tint and the alpha of the options to a color.
+fn tint_color(color: Rgba<u8>, options: &CalculationOptions) -> Rgba<u8> {
+    let mut color = match options.tint {
+        Some(ref tint) => multiply_color(color, tint.clone()),
+        None => color
     };
 
-    
-    // returning
-    Ok((color, attachment))
+    if let Some(alpha) = options.alpha {
+        color.a = (color.a as f32 * alpha.max(0.0).min(1.0)).round() as u8;
+    }
+
+    color
+}
+
+/// Multiplies the red, green and blue components of a color by its alpha component.
+fn premultiply_color(color: Rgba<u8>) -> Rgba<u8> {
+    let multiply = |c: u8| ((c as u32 * color.a as u32 + 127) / 255) as u8;
+
+    Rgba {
+        a: color.a,
+        c: Rgb::new(multiply(color.c.r), multiply(color.c.g), multiply(color.c.b)),
+    }
+}
+
+/// Multiplies each component of two colors, as if they were between 0 and 1.
+fn multiply_color(color: Rgba<u8>, tint: Rgba<u8>) -> Rgba<u8> {
+    let multiply = |a: u8, b: u8| ((a as u32 * b as u32 + 127) / 255) as u8;
+
+    Rgba {
+        a: multiply(color.a, tint.a),
+        c: Rgb::new(multiply(color.c.r, tint.c.r), multiply(color.c.g, tint.c.g),
+                    multiply(color.c.b, tint.c.b)),
+    }
+}
+
+/// Linearly interpolates each component of a color. The components are rounded to the nearest
+///  integer.
+fn mix_color(from: Rgba<u8>, to: Rgba<u8>, weight: f32) -> Rgba<u8> {
+    let component = |from: u8, to: u8| {
+        (from as f32 + (to as f32 - from as f32) * weight).round().max(0.0).min(255.0) as u8
+    };
+
+    Rgba {
+        a: component(from.a, to.a),
+        c: Rgb::new(component(from.c.r, to.c.r), component(from.c.g, to.c.g),
+                    component(from.c.b, to.c.b)),
+    }
+}
+
+/// Interpolates two 2D transformations, rotating their axes along the shortest arc.
+///
+/// Interpolating the components instead would shrink the sprites in the middle of a rotation.
+fn mix_matrix(from: &Matrix4<f32>, to: &Matrix4<f32>, weight: f32) -> Matrix4<f32> {
+    use std::f32::consts::PI;
+
+    let axis = |from: (f32, f32), to: (f32, f32)| {
+        let from_angle = from.1.atan2(from.0);
+        let mut delta = to.1.atan2(to.0) - from_angle;
+        if delta > PI { delta -= 2.0 * PI; }
+        if delta < -PI { delta += 2.0 * PI; }
+
+        let from_length = (from.0 * from.0 + from.1 * from.1).sqrt();
+        let to_length = (to.0 * to.0 + to.1 * to.1).sqrt();
+        let angle = from_angle + delta * weight;
+        let length = from_length + (to_length - from_length) * weight;
+        (angle.cos() * length, angle.sin() * length)
+    };
+
+    let mut result = from.clone();
+    let x = axis((from.x.x, from.x.y), (to.x.x, to.x.y));
+    let y = axis((from.y.x, from.y.y), (to.y.x, to.y.y));
+    result.x.x = x.0;
+    result.x.y = x.1;
+    result.y.x = y.0;
+    result.y.y = y.1;
+    result.w.x = from.w.x + (to.w.x - from.w.x) * weight;
+    result.w.y = from.w.y + (to.w.y - from.w.y) * weight;
+    result
+}
+
+/// Interpolates each component of a color using the value of a "curve" member.
+///
+/// A bezier curve with more than 4 numbers, converted from Spine 4, contains a curve for each
+///  channel in the red, green, blue and alpha order. The components are rounded to the nearest
+///  integer.
+fn interpolate_color<'a>(formula: &'a Option<format::TimelineCurve>, from: Rgba<u8>,
+    to: Rgba<u8>, position: f32, options: &CalculationOptions)
+    -> Result<Rgba<u8>, CalculationError<'a>>
+{
+    let component = |channel: usize, from: u8, to: u8| -> Result<u8, CalculationError<'a>> {
+        let (from, to) = (from as f32, to as f32);
+        let value = match formula {
+            &Some(format::TimelineCurve::CurveBezier(ref points)) if points.len() > 4 => {
+                if points.len() >= channel * 4 + 4 {
+                    let points = &points[channel * 4 .. channel * 4 + 4];
+                    from + calculate_bezier(points, position) * (to - from)
+                } else {
+                    from + position * (to - from)
+                }
+            },
+            _ => try!(calculate_curve(formula, from, to, position, options))
+        };
+        Ok(value.round().max(0.0).min(255.0) as u8)
+    };
+
+    Ok(Rgba {
+        a: try!(component(3, from.a, to.a)),
+        c: Rgb::new(try!(component(0, from.c.r, to.c.r)), try!(component(1, from.c.g, to.c.g)),
+                    try!(component(2, from.c.b, to.c.b))),
+    })
 }
@@ -0,0 +1,272 @@
+use std::collections::VecDeque;
+
+use super::{Calculation, CalculationError, CalculationOptions, EMPTY_ANIMATION, Layer,
+            SpineDocument};
+
+/// Plays animations one after the other, crossfading between them.
+///
+/// Animations are played on tracks, each with its own queue and its own time. The tracks are
+///  applied in order: a track replaces the bones and slots that its animations animate,
+///  weighted by the alpha of the track, and leaves the others untouched. For example track 0
+///  can play a walk animation while track 1 plays a shooting animation that only moves the arms.
+///
+/// The state doesn't hold a reference to the document. Instead you must pass the document
+///  to `update` and `calculate`, which means that a single document can be shared by several
+///  states, for example one per character.
+///
+/// ```no_run
+/// # let document: spine::SpineDocument = unsafe { std::mem::uninitialized() };
+/// let mut state = spine::AnimationState::new();
+/// state.set_animation(0, "jump", 0.0);
+/// state.add_animation(0, "fall", 0.2);
+/// state.add_animation(0, "land", 0.1);
+///
+/// // at each frame
+/// state.update(&document, 0.016);
+/// let results = state.calculate(&document, "default").unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AnimationState {
+    /// The tracks, from the lowest to the highest.
+    tracks: Vec<Track>,
+}
+
+/// Animations played on one track of an `AnimationState`.
+#[derive(Debug, Clone)]
+struct Track {
+    /// Animation being played and the time elapsed in it.
+    current: Option<(String, f32)>,
+
+    /// Animation that is being faded out and the time at which it was left.
+    previous: Option<(String, f32)>,
+
+    /// Time elapsed since the beginning of the crossfade.
+    mix_time: f32,
+
+    /// Duration of the crossfade.
+    mix_duration: f32,
+
+    /// Animations that will be played when the current one is over, with their mix duration.
+    queue: VecDeque<(String, f32)>,
+
+    /// Time of the current animation at which the first animation of the queue was queued.
+    ///
+    /// The current animation is over at the end of the loop that was being played at this time.
+    queued_at: f32,
+
+    /// Weight of the track over the lower ones.
+    alpha: f32,
+}
+
+impl AnimationState {
+    /// Builds a new state that doesn't play any animation.
+    pub fn new() -> AnimationState {
+        Default::default()
+    }
+
+    /// Returns the name of the animation being played on a track.
+    pub fn get_current_animation(&self, track: usize) -> Option<&str> {
+        self.tracks.get(track).and_then(|t| t.current.as_ref()).map(|&(ref name, _)| &name[..])
+    }
+
+    /// Returns the time elapsed in the animation being played on a track.
+    ///
+    /// Stays at 0 if there is no animation.
+    pub fn get_current_time(&self, track: usize) -> f32 {
+        self.tracks.get(track).and_then(|t| t.current.as_ref()).map(|&(_, time)| time)
+            .unwrap_or(0.0)
+    }
+
+    /// Immediately plays an animation on a track, crossfading from the current one during
+    ///  `mix_duration` seconds. The animations queued on this track are discarded.
+    pub fn set_animation(&mut self, track: usize, animation: &str, mix_duration: f32) {
+        let track = self.get_track(track);
+        track.queue.clear();
+        track.start(animation.to_string(), mix_duration, 0.0);
+    }
+
+    /// Queues an animation that will be played on a track when all the previous ones of this
+    ///  track are over. The crossfade from the previous animation lasts `mix_duration` seconds.
+    ///
+    /// If no animation is being played on the track, it starts immediately. The last animation
+    ///  of the queue loops until another one is set or queued. If it has already looped, the
+    ///  queued animation starts at the end of its current loop.
+    pub fn add_animation(&mut self, track: usize, animation: &str, mix_duration: f32) {
+        let track = self.get_track(track);
+        match track.current {
+            None => track.start(animation.to_string(), mix_duration, 0.0),
+            Some((_, time)) => {
+                if track.queue.is_empty() {
+                    track.queued_at = time;
+                }
+                track.queue.push_back((animation.to_string(), mix_duration));
+            }
+        }
+    }
+
+    /// Immediately crossfades the animation of a track to the setup pose during `mix_duration`
+    ///  seconds. The animations queued on this track are discarded.
+    ///
+    /// This plays `EMPTY_ANIMATION`, so the track doesn't affect the lower ones once the
+    ///  crossfade is over.
+    pub fn set_empty_animation(&mut self, track: usize, mix_duration: f32) {
+        self.set_animation(track, EMPTY_ANIMATION, mix_duration);
+    }
+
+    /// Queues a crossfade to the setup pose that will happen on a track when all the previous
+    ///  animations of this track are over. Same as `add_animation` with `EMPTY_ANIMATION`.
+    pub fn add_empty_animation(&mut self, track: usize, mix_duration: f32) {
+        self.add_animation(track, EMPTY_ANIMATION, mix_duration);
+    }
+
+    /// Stops playing animations on a track and discards its queue.
+    pub fn clear_track(&mut self, track: usize) {
+        if let Some(track) = self.tracks.get_mut(track) {
+            track.current = None;
+            track.previous = None;
+            track.queue.clear();
+        }
+    }
+
+    /// Sets the weight of a track over the lower ones, between 0 and 1. Defaults to 1.
+    pub fn set_track_alpha(&mut self, track: usize, alpha: f32) {
+        self.get_track(track).alpha = alpha;
+    }
+
+    /// Advances the time by `delta` seconds, switching to the queued animations whose turn
+    ///  has come.
+    ///
+    /// The state never reads a clock by itself. Passing fixed or scaled deltas gives you
+    ///  deterministic or slowed down playbacks.
+    pub fn update(&mut self, document: &SpineDocument, delta: f32) {
+        for track in self.tracks.iter_mut() {
+            track.update(document, delta);
+        }
+    }
+
+    /// Calculates the sprites to draw, in the same way as `SpineDocument::calculate`.
+    pub fn calculate<'a>(&self, document: &'a SpineDocument, skin: &str)
+        -> Result<Calculation<'a>, CalculationError<'a>>
+    {
+        self.calculate_with_options(document, skin, &Default::default())
+    }
+
+    /// Same as `calculate`, but with additional options.
+    pub fn calculate_with_options<'a>(&self, document: &'a SpineDocument, skin: &str,
+        options: &CalculationOptions) -> Result<Calculation<'a>, CalculationError<'a>>
+    {
+        let layers: Vec<Layer> = self.tracks.iter()
+                                            .filter_map(|track| track.to_layer(document))
+                                            .collect();
+        document.calculate_layers(skin, &layers, options)
+    }
+
+    /// Returns a track, creating it and the ones below it if needed.
+    fn get_track(&mut self, track: usize) -> &mut Track {
+        while self.tracks.len() <= track {
+            self.tracks.push(Track::new());
+        }
+
+        &mut self.tracks[track]
+    }
+}
+
+impl Track {
+    /// Builds a track that doesn't play any animation.
+    fn new() -> Track {
+        Track {
+            current: None,
+            previous: None,
+            mix_time: 0.0,
+            mix_duration: 0.0,
+            queue: VecDeque::new(),
+            queued_at: 0.0,
+            alpha: 1.0,
+        }
+    }
+
+    /// Advances the time of the track by `delta` seconds.
+    fn update(&mut self, document: &SpineDocument, delta: f32) {
+        if let Some((_, ref mut time)) = self.current {
+            *time += delta;
+        }
+        self.mix_time += delta;
+
+        loop {
+            // the time elapsed since the end of the current animation, if it is over
+            let overflow = match self.current {
+                Some((ref name, time)) => match document.get_animation_duration(name) {
+                    Some(duration) if !self.queue.is_empty() => {
+                        // the loop that was being played when the next animation was queued
+                        let loops = if duration > 0.0 {
+                            (self.queued_at / duration).floor()
+                        } else {
+                            0.0
+                        };
+
+                        let end = duration * (loops + 1.0);
+                        if time < end {
+                            break;
+                        }
+
+                        time - end
+                    },
+                    _ => break
+                },
+                None => break
+            };
+
+            // the previous animation is faded out from its last frame
+            if let Some((ref name, ref mut time)) = self.current {
+                *time = document.get_animation_duration(name).unwrap_or(*time);
+            }
+
+            let (next, mix_duration) = self.queue.pop_front().unwrap();
+            self.start(next, mix_duration, overflow);
+        }
+
+        if self.mix_time >= self.mix_duration {
+            self.previous = None;
+        }
+    }
+
+    /// Returns the layer to calculate, or `None` if no animation is being played.
+    fn to_layer<'a>(&'a self, document: &SpineDocument) -> Option<Layer<'a>> {
+        // the last animation loops
+        let (animation, elapsed) = match self.current {
+            Some((ref name, time)) => match document.get_animation_duration(name) {
+                Some(duration) if duration > 0.0 => (&name[..], time % duration),
+                _ => (&name[..], time)
+            },
+            None => return None
+        };
+
+        // the previous animation may have been interrupted after having looped
+        let mix = match self.previous {
+            Some((ref name, time)) if self.mix_time < self.mix_duration => {
+                let time = match document.get_animation_duration(name) {
+                    Some(duration) if time > duration => time % duration,
+                    _ => time
+                };
+                Some((&name[..], time, self.mix_time / self.mix_duration))
+            },
+            _ => None
+        };
+
+        Some(Layer {
+            animation: animation,
+            elapsed: elapsed,
+            mix: mix,
+            alpha: self.alpha.max(0.0).min(1.0),
+        })
+    }
+
+    /// Starts playing an animation at the given time. The crossfade starts from the beginning.
+    fn start(&mut self, animation: String, mix_duration: f32, time: f32) {
+        self.previous = self.current.take();
+        self.queued_at = 0.0;
+        self.mix_time = 0.0;
+        self.mix_duration = mix_duration;
+        self.current = Some((animation, time));
+    }
+}
@@ -1,3 +1,5 @@
+extern crate cgmath;
+extern crate color;
 extern crate spine;
 
 use std::io::BufReader;
@@ -15,6 +17,7 @@ fn animations_list() {
     assert!(doc.has_animation("walk"));
     assert!(doc.has_animation("jump"));
     assert!(!doc.has_animation("crawl"));
+    assert!(doc.get_animations_count() == 2);
 }
 
 #[test]
@@ -42,3 +45,2701 @@ fn possible_sprites() {
         "right-hand", "right-lower-leg", "right-shoulder", "right-upper-leg", "torso"
     ]);
 }
+
+#[test]
+fn hidden_slot_shown_by_timeline() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [ { "name": "slot", "bone": "root", "attachment": null } ],
+        "skins": { "default": { "slot": { "image": { "width": 10, "height": 10 } } } },
+        "animations": {
+            "appear": {
+                "slots": {
+                    "slot": {
+                        "attachment": [
+                            { "time": 0.5, "name": "image" },
+                            { "time": 1, "name": "image" }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    assert!(doc.calculate("default", None, 0.0).unwrap().sprites.len() == 0);
+    assert!(doc.calculate("default", Some("appear"), 0.25).unwrap().sprites.len() == 0);
+
+    let results = doc.calculate("default", Some("appear"), 0.75).unwrap();
+    assert!(results.sprites.len() == 1);
+    assert!(results.sprites[0].name == "image");
+}
+
+#[test]
+fn keyframe_times() {
+    let src: &[u8] = include_bytes!("example.json");
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let times = doc.get_animation_keyframe_times("walk").unwrap();
+    assert!(times == [0.0, 0.1333, 0.1666, 0.2666, 0.4, 0.5333, 0.6666, 0.7, 0.8, 0.9333, 1.0666]);
+
+    assert!(doc.get_animation_event_times("walk").unwrap().len() == 0);
+    assert!(doc.get_animation_keyframe_times("crawl").is_none());
+    assert!(doc.get_animation_event_times("crawl").is_none());
+}
+
+#[test]
+fn animation_start_time() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "animations": {
+            "late": {
+                "bones": {
+                    "root": {
+                        "rotate": [
+                            { "time": 0.5, "angle": 0 },
+                            { "time": 1, "angle": 90 }
+                        ]
+                    }
+                }
+            },
+            "empty": {}
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    assert!(doc.get_animation_start_time("late") == Some(0.5));
+    assert!(doc.get_animation_duration("late") == Some(1.0));
+    assert!(doc.get_animation_start_time("empty") == Some(0.0));
+    assert!(doc.get_animation_start_time("crawl").is_none());
+}
+
+#[test]
+fn would_change() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "animations": {
+            "wait": {
+                "bones": {
+                    "root": {
+                        "translate": [
+                            { "time": 0, "x": 0, "y": 0 },
+                            { "time": 1, "x": 10, "y": 0, "curve": "stepped" },
+                            { "time": 2, "x": 0, "y": 0 },
+                            { "time": 3, "x": 0, "y": 0 }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    assert!(doc.would_change("wait", 0.25, 0.5));
+    assert!(!doc.would_change("wait", 1.25, 1.5));
+    assert!(doc.would_change("wait", 1.5, 2.0));
+    assert!(!doc.would_change("wait", 3.0, 3.5));
+    assert!(doc.would_change("wait", 0.5, 0.25));
+    assert!(!doc.would_change("crawl", 0.25, 0.5));
+}
+
+#[test]
+fn color_fade_is_rounded() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [ { "name": "slot", "bone": "root", "attachment": "image" } ],
+        "skins": { "default": { "slot": { "image": { "width": 10, "height": 10 } } } },
+        "animations": {
+            "fade": {
+                "slots": {
+                    "slot": {
+                        "color": [
+                            { "time": 0, "color": "00000000" },
+                            { "time": 1, "color": "FFFFFFFF" }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let results = doc.calculate("default", Some("fade"), 0.5).unwrap();
+    let color = &results.sprites[0].color;
+    assert!(color.a == 128);
+    assert!(color.c.r == 128 && color.c.g == 128 && color.c.b == 128);
+}
+
+#[test]
+fn convex_hull() {
+    let src: &[u8] = br#"{
+        "bones": [
+            { "name": "root" },
+            { "name": "arm", "parent": "root", "x": 20, "rotation": 45 }
+        ],
+        "slots": [
+            { "name": "body", "bone": "root", "attachment": "body" },
+            { "name": "arm", "bone": "arm", "attachment": "arm" },
+            { "name": "hitbox", "bone": "root", "attachment": "hitbox" }
+        ],
+        "skins": { "default": {
+            "body": { "body": { "width": 20, "height": 20 } },
+            "arm": { "arm": { "width": 4, "height": 4 } },
+            "hitbox": { "hitbox": { "type": "boundingbox", "width": 100, "height": 100 } }
+        } }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let hull = doc.calculate("default", None, 0.0).unwrap().get_convex_hull();
+    assert!(hull.len() == 7);
+
+    // the points are in counter-clockwise order
+    for i in 0 .. hull.len() {
+        let (a, b, c) = (hull[i], hull[(i + 1) % hull.len()], hull[(i + 2) % hull.len()]);
+        assert!((b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0) > 0.0);
+    }
+
+    // the bounding box isn't included
+    assert!(hull.iter().all(|&(x, y)| x.abs() < 25.0 && y.abs() < 25.0));
+
+    // the corners that aren't finite are ignored
+    let mut options = spine::CalculationOptions::default();
+    options.bone_overrides.insert("arm".to_string(), spine::BoneOverride {
+        position: Some((::std::f32::NAN, 0.0)),
+        .. Default::default()
+    });
+    let hull = doc.calculate_with_options("default", None, 0.0, &options).unwrap()
+                  .get_convex_hull();
+    assert!(hull.len() == 4);
+}
+
+#[test]
+fn tint() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [ { "name": "slot", "bone": "root", "attachment": "image", "color": "FF808080" } ],
+        "skins": { "default": { "slot": { "image": { "width": 10, "height": 10 } } } }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let mut options = spine::CalculationOptions::default();
+    options.tint = Some(color::Rgba { a: 255, c: color::Rgb::new(255, 255, 255) });
+    let white = doc.calculate_with_options("default", None, 0.0, &options).unwrap();
+    assert!(white == doc.calculate("default", None, 0.0).unwrap());
+
+    options.tint = Some(color::Rgba { a: 255, c: color::Rgb::new(255, 0, 0) });
+    let results = doc.calculate_with_options("default", None, 0.0, &options).unwrap();
+    let color = &results.sprites[0].color;
+    assert!(color.c.r == 255 && color.c.g == 0 && color.c.b == 0);
+    assert!(color.a == 128);
+}
+
+#[test]
+fn split_color_timelines() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [ { "name": "slot", "bone": "root", "attachment": "image", "color": "FF000080" } ],
+        "skins": { "default": { "slot": { "image": { "width": 10, "height": 10 } } } },
+        "animations": {
+            "tint": {
+                "slots": {
+                    "slot": {
+                        "rgb": [
+                            { "time": 0, "color": "00FF00" },
+                            { "time": 1, "color": "0000FF" }
+                        ]
+                    }
+                }
+            },
+            "fade": {
+                "slots": {
+                    "slot": {
+                        "alpha": [
+                            { "time": 0, "value": 1 },
+                            { "time": 1, "value": 0 }
+                        ]
+                    }
+                }
+            },
+            "flash": {
+                "slots": {
+                    "slot": {
+                        "rgba": [
+                            { "time": 0, "color": "FFFFFFFF" },
+                            { "time": 1, "color": "FFFFFFFF" }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    // the alpha of the setup pose is kept
+    let results = doc.calculate("default", Some("tint"), 0.0).unwrap();
+    let color = &results.sprites[0].color;
+    assert!(color.c.r == 0 && color.c.g == 255 && color.c.b == 0 && color.a == 128);
+
+    // the red, green and blue of the setup pose are kept
+    let results = doc.calculate("default", Some("fade"), 0.5).unwrap();
+    let color = &results.sprites[0].color;
+    assert!(color.c.r == 255 && color.c.g == 0 && color.c.b == 0 && color.a == 128);
+
+    let results = doc.calculate("default", Some("flash"), 0.5).unwrap();
+    let color = &results.sprites[0].color;
+    assert!(color.c.r == 255 && color.c.g == 255 && color.c.b == 255 && color.a == 255);
+    assert!(doc.get_animation_duration("fade") == Some(1.0));
+}
+
+#[test]
+fn validate() {
+    let src: &[u8] = include_bytes!("example.json");
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+    assert!(doc.validate().len() == 0);
+
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [ { "name": "slot", "bone": "root", "attachment": "image" } ],
+        "skins": { "default": { "slot": {
+            "image": { "width": 10 },
+            "hitbox": { "type": "boundingbox" }
+        } } }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+    assert!(doc.validate() == ["attachment `image` of slot `slot` in skin `default` has no area"]);
+
+    // skins that reference slots that don't exist are loaded anyway
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "skins": { "default": { "cape": { "cape": { "width": 10, "height": 10 } } } }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+    assert!(doc.validate() == ["slot `cape` of skin `default` not found"]);
+    assert!(doc.calculate("default", None, 0.0).unwrap().sprites.is_empty());
+}
+
+#[test]
+fn split_color_curves() {
+    let src = |curve: &str| format!(r#"{{
+        "bones": [ {{ "name": "root" }} ],
+        "slots": [ {{ "name": "slot", "bone": "root", "attachment": "image" }} ],
+        "skins": {{ "default": {{ "slot": {{ "image": {{ "width": 10, "height": 10 }} }} }} }},
+        "animations": {{
+            "tint": {{
+                "slots": {{
+                    "slot": {{
+                        "rgb": [
+                            {{ "color": "000000", "curve": [{}] }},
+                            {{ "time": 1, "color": "FFFFFF" }}
+                        ]
+                    }}
+                }}
+            }},
+            "fade": {{
+                "slots": {{
+                    "slot": {{
+                        "alpha": [
+                            {{ "value": 1, "curve": [0.25, 1, 0.75, 1] }},
+                            {{ "time": 1, "value": 0 }}
+                        ]
+                    }}
+                }}
+            }}
+        }}
+    }}"#, curve);
+
+    // the control points are absolute, with a curve per channel
+    let curve = "0.25, 0.25, 0.75, 0.75, 0.25, 0, 0.75, 0, 0.25, 1, 0.75, 1";
+    let doc = spine::SpineDocument::new(BufReader::new(src(curve).as_bytes())).unwrap();
+    assert!(doc.get_animation_duration("fade") == Some(1.0));
+
+    let results = doc.calculate("default", Some("tint"), 0.5).unwrap();
+    let color = &results.sprites[0].color;
+    assert!(color.c.r >= 127 && color.c.r <= 128 && color.c.g == 32 && color.c.b == 223);
+
+    let results = doc.calculate("default", Some("fade"), 0.5).unwrap();
+    assert!(results.sprites[0].color.a == 223);
+
+    // the curves are written in the format they were loaded from
+    let mut output = Vec::new();
+    doc.to_json_writer(&mut output).unwrap();
+    let reloaded = spine::SpineDocument::new(BufReader::new(&output[..])).unwrap();
+    for &(animation, time) in [("tint", 0.5), ("tint", 0.2), ("fade", 0.5)].iter() {
+        let original = doc.calculate("default", Some(animation), time).unwrap();
+        let results = reloaded.calculate("default", Some(animation), time).unwrap();
+        assert!(results.sprites[0].color == original.sprites[0].color);
+    }
+
+    match spine::SpineDocument::new(BufReader::new(src("0.25, 0, 0.75, 1").as_bytes())) {
+        Err(spine::LoadError::Format(_)) => (),
+        _ => panic!()
+    }
+}
+
+#[test]
+fn attachment_path() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [ { "name": "eye", "bone": "root", "attachment": "eye-open" } ],
+        "skins": {
+            "default": {
+                "eye": { "eye-open": { "path": "eyes/open", "width": 10, "height": 10 } }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    assert!(doc.get_possible_sprites() == ["eyes/open"]);
+
+    let results = doc.calculate("default", None, 0.0).unwrap();
+    assert!(results.sprites[0].name == "eyes/open");
+}
+
+#[test]
+fn document_with_json() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "ik": [ { "name": "aim", "bones": [ "root" ], "target": "root" } ]
+    }"#;
+    let (doc, json) = spine::SpineDocument::new_with_json(BufReader::new(src)).unwrap();
+
+    assert!(doc.get_bones_list() == ["root"]);
+    assert!(json.find("ik").and_then(|ik| ik.as_array()).map(|ik| ik.len()) == Some(1));
+}
+
+#[test]
+fn load_errors() {
+    let src: &[u8] = b"{ \"bones\": [ ";
+    match spine::SpineDocument::new(BufReader::new(src)) {
+        Err(spine::LoadError::Json(_)) => (),
+        _ => panic!()
+    };
+
+    let src: &[u8] = b"{ \"bones\": 5 }";
+    match spine::SpineDocument::new(BufReader::new(src)) {
+        Err(spine::LoadError::Format(_)) => (),
+        _ => panic!()
+    };
+}
+
+#[test]
+fn io_error() {
+    struct FailingReader;
+
+    impl std::io::Read for FailingReader {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::new(std::io::ErrorKind::ConnectionReset, "connection reset"))
+        }
+    }
+
+    match spine::SpineDocument::new(FailingReader) {
+        Err(spine::LoadError::Io(std::io::ErrorKind::ConnectionReset, _)) => (),
+        _ => panic!()
+    };
+}
+
+#[test]
+fn calculation_error_kind() {
+    let src: &[u8] = include_bytes!("example.json");
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let err = doc.calculate("default", Some("crawl"), 0.0).unwrap_err();
+    assert!(err == spine::CalculationError::AnimationNotFound);
+    assert!(!err.is_document_error());
+}
+
+#[test]
+fn bones_matrices() {
+    let src: &[u8] = include_bytes!("example.json");
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let bones = doc.calculate_bones(Some("walk"), 0.3).unwrap();
+    assert!(bones.len() == 18);
+    assert!(bones[0].0 == "root");
+    assert!(bones[1].0 == "hip");
+
+    assert!(doc.calculate_bones(Some("crawl"), 0.3).is_err());
+}
+
+#[test]
+fn ragdoll_snapshot() {
+    let src: &[u8] = br#"{
+        "bones": [
+            { "name": "root" },
+            { "name": "arm", "parent": "root", "x": 5, "length": 20 }
+        ]
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let snapshot = doc.calculate_ragdoll_snapshot(None, 0.0).unwrap();
+    assert!(snapshot.len() == 2);
+    assert!(snapshot[0].0 == "root" && snapshot[0].2 == 0.0);
+    assert!(snapshot[1].0 == "arm" && snapshot[1].2 == 20.0);
+    assert!((snapshot[1].1.w.x - 5.0).abs() < 0.001);
+}
+
+#[test]
+fn bone_override() {
+    let src: &[u8] = include_bytes!("example.json");
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let mut options = spine::CalculationOptions::default();
+    options.bone_overrides.insert("root".to_string(), spine::BoneOverride {
+        position: Some((100.0, 0.0)),
+        .. Default::default()
+    });
+
+    let normal = doc.calculate_bones(Some("walk"), 0.3).unwrap();
+    let overridden = doc.calculate_bones_with_options(Some("walk"), 0.3, &options).unwrap();
+
+    // "hip" is a child of "root" and must inherit the override
+    assert!(overridden[1].0 == "hip");
+    assert!((overridden[1].1.w.x - normal[1].1.w.x - 100.0).abs() < 0.001);
+    assert!((overridden[1].1.w.y - normal[1].1.w.y).abs() < 0.001);
+}
+
+#[test]
+fn event_audio() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "events": {
+            "footstep": { "int": 2, "audio": "sounds/step.ogg", "volume": 0.5 },
+            "shout": {}
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let footstep = doc.get_event("footstep").unwrap();
+    assert!(footstep.int == 2);
+    assert!(footstep.audio == Some("sounds/step.ogg"));
+    assert!(footstep.volume == 0.5);
+    assert!(footstep.balance == 0.0);
+
+    let shout = doc.get_event("shout").unwrap();
+    assert!(shout.audio.is_none());
+    assert!(shout.volume == 1.0);
+
+    assert!(doc.get_event("jump").is_none());
+}
+
+#[test]
+fn infinite_number() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root", "rotation": 1e999 } ]
+    }"#;
+
+    match spine::SpineDocument::new(BufReader::new(src)) {
+        Err(spine::LoadError::InvalidNumber(_)) => (),
+        _ => panic!()
+    };
+}
+
+#[test]
+fn pose_roundtrip() {
+    let src: &[u8] = include_bytes!("example.json");
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let results = doc.calculate("default", Some("walk"), 0.3).unwrap();
+    let pose = results.to_pose();
+    assert!(pose.clone() == pose);
+
+    let restored = pose.to_calculation();
+    assert!(restored.sprites.len() == results.sprites.len());
+    for (a, b) in restored.sprites.iter().zip(results.sprites.iter()) {
+        assert!(a.name == b.name);
+        assert!(a.matrix == b.matrix);
+    }
+}
+
+#[test]
+fn attachment_sequence() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [ { "name": "fire", "bone": "root", "attachment": "flame" } ],
+        "skins": {
+            "default": {
+                "fire": {
+                    "flame": {
+                        "path": "flame/", "width": 10, "height": 10,
+                        "sequence": { "count": 3, "digits": 2 }
+                    }
+                }
+            }
+        },
+        "animations": {
+            "burn": {
+                "attachments": {
+                    "default": {
+                        "fire": {
+                            "flame": {
+                                "sequence": [
+                                    { "time": 0, "mode": "loop", "delay": 0.1 },
+                                    { "time": 1 }
+                                ]
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    assert!(doc.get_possible_sprites() == ["flame/01", "flame/02", "flame/03"]);
+    assert!(doc.get_animation_duration("burn") == Some(1.0));
+
+    assert!(doc.calculate("default", None, 0.0).unwrap().sprites[0].name == "flame/01");
+    assert!(doc.calculate("default", Some("burn"), 0.15).unwrap().sprites[0].name == "flame/02");
+    assert!(doc.calculate("default", Some("burn"), 0.35).unwrap().sprites[0].name == "flame/01");
+}
+
+#[test]
+fn sequence_limits() {
+    let doc = |count: &str, mode: &str, delay: &str| {
+        let src = format!(r#"{{
+            "bones": [ {{ "name": "root" }} ],
+            "slots": [ {{ "name": "fire", "bone": "root", "attachment": "flame" }} ],
+            "skins": {{ "default": {{ "fire": {{
+                "flame": {{ "width": 10, "height": 10, "sequence": {{ "count": {} }} }}
+            }} }} }},
+            "animations": {{
+                "burn": {{
+                    "attachments": {{ "default": {{ "fire": {{ "flame": {{
+                        "sequence": [
+                            {{ "time": 0, "mode": "{}", "delay": {}, "index": 1 }},
+                            {{ "time": 1 }}
+                        ]
+                    }} }} }} }}
+                }}
+            }}
+        }}"#, count, mode, delay);
+        spine::SpineDocument::new(BufReader::new(src.as_bytes()))
+    };
+
+    // a tiny delay gives a huge number of steps
+    let modes = ["loop", "pingpong", "pingpongReverse", "loopReverse", "once", "random"];
+    for mode in modes.iter() {
+        let doc = doc("3", mode, "1e-12").unwrap();
+        assert!(doc.calculate("default", Some("burn"), 0.9).is_ok());
+    }
+
+    for &(count, delay) in [("2000000000", "0.1"), ("3", "0"), ("3", "-0.1")].iter() {
+        match doc(count, "loop", delay) {
+            Err(spine::LoadError::InvalidNumber(_)) => (),
+            _ => panic!()
+        }
+    }
+}
+
+#[test]
+fn slot_animated() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [
+            { "name": "a", "bone": "root" },
+            { "name": "b", "bone": "root" }
+        ],
+        "animations": {
+            "blink": {
+                "slots": {
+                    "a": {
+                        "color": [
+                            { "time": 0.25, "color": "FFFFFFFF" },
+                            { "time": 0.5, "color": "FFFFFF00" }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    assert!(!doc.is_slot_animated("blink", "a", 0.1));
+    assert!(doc.is_slot_animated("blink", "a", 0.3));
+    assert!(!doc.is_slot_animated("blink", "b", 0.3));
+    assert!(!doc.is_slot_animated("walk", "a", 0.3));
+}
+
+#[test]
+fn memory_usage() {
+    let src: &[u8] = include_bytes!("example.json");
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let src: &[u8] = br#"{ "bones": [ { "name": "root" } ] }"#;
+    let small = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    assert!(small.get_memory_usage() > 0);
+    assert!(doc.get_memory_usage() > small.get_memory_usage());
+}
+
+#[test]
+fn premultiplied_alpha() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [ { "name": "slot", "bone": "root", "color": "FF402080", "attachment": "image" } ],
+        "skins": { "default": { "slot": { "image": { "width": 10, "height": 10 } } } }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let results = doc.calculate("default", None, 0.0).unwrap();
+    let color = &results.sprites[0].color;
+    assert!(color.c.r == 255 && color.c.g == 64 && color.c.b == 32 && color.a == 128);
+
+    let mut options = spine::CalculationOptions::default();
+    options.premultiplied_alpha = true;
+    let results = doc.calculate_with_options("default", None, 0.0, &options).unwrap();
+    let color = &results.sprites[0].color;
+    assert!(color.c.r == 128 && color.c.g == 32 && color.c.b == 16 && color.a == 128);
+}
+
+#[test]
+fn replace_animation() {
+    let src: &[u8] = include_bytes!("example.json");
+    let mut doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let anim: &[u8] = br#"{ "bones": { "hip": { "rotate": [ { "time": 0, "angle": 0 },
+                                                              { "time": 2, "angle": 90 } ] } } }"#;
+    doc.replace_animation("walk", BufReader::new(anim)).unwrap();
+    assert!(doc.get_animation_duration("walk") == Some(2.0));
+
+    let anim: &[u8] = br#"{ "bones": { "tail": { "rotate": [ { "time": 3, "angle": 0 } ] } } }"#;
+    match doc.replace_animation("walk", BufReader::new(anim)) {
+        Err(spine::LoadError::BoneNotFound(ref bone)) if bone == "tail" => (),
+        _ => panic!()
+    };
+    assert!(doc.get_animation_duration("walk") == Some(2.0));
+}
+
+#[test]
+fn bones_hierarchy() {
+    let src: &[u8] = include_bytes!("example.json");
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    assert!(doc.get_bones_list().len() == 18);
+    assert!(doc.get_bone_parent("root").is_none());
+    assert!(doc.get_bone_parent("neck") == Some("torso"));
+    assert!(doc.get_bone_parent("tail").is_none());
+
+    assert!(doc.get_bone_children("torso") == ["neck", "right shoulder", "left shoulder"]);
+    assert!(doc.get_bone_descendants("right shoulder") == ["right arm", "right hand"]);
+    assert!(doc.get_bone_descendants("root").len() == 17);
+}
+
+#[test]
+fn slots_bones() {
+    let src: &[u8] = include_bytes!("example.json");
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    assert!(doc.get_slot_bone("eyes") == Some("head"));
+    assert!(doc.get_slot_bone("tail").is_none());
+
+    let slots_bones = doc.get_slots_bones();
+    assert!(slots_bones.len() == doc.get_slots_list().len());
+    assert!(slots_bones[0] == ("left shoulder", "left shoulder"));
+}
+
+#[test]
+fn sprite_slot() {
+    let src: &[u8] = include_bytes!("example.json");
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let results = doc.calculate("default", Some("walk"), 0.3).unwrap();
+    let sprite = results.sprites.iter().find(|s| s.slot == "eyes").unwrap();
+    assert!(sprite.name == "eyes");
+    assert!(results.to_pose().sprites.iter().any(|s| s.slot == "eyes"));
+}
+
+#[test]
+fn load_in_thread() {
+    fn is_send_sync<T: Send + Sync>() {}
+    is_send_sync::<spine::SpineDocument>();
+
+    let doc = std::thread::spawn(|| {
+        let src: &[u8] = include_bytes!("example.json");
+        spine::SpineDocument::new(BufReader::new(src)).unwrap()
+    }).join().unwrap();
+
+    assert!(doc.has_animation("walk"));
+}
+
+#[test]
+fn duplicate_keyframe_times() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "animations": {
+            "snap": {
+                "bones": {
+                    "root": {
+                        "rotate": [
+                            { "time": 0, "angle": 0 },
+                            { "time": 0.5, "angle": 10 },
+                            { "time": 0.5, "angle": 90 },
+                            { "time": 1, "angle": 90 }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let bones = doc.calculate_bones(Some("snap"), 0.5).unwrap();
+    let matrix = &bones[0].1;
+    assert!(matrix.x.x.is_finite() && matrix.x.y.is_finite());
+    assert!(matrix.x.x.abs() < 0.001);
+    assert!((matrix.x.y - 1.0).abs() < 0.001);
+}
+
+#[test]
+fn json_roundtrip() {
+    let src: &[u8] = include_bytes!("example.json");
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let mut output = Vec::new();
+    doc.to_json_writer(&mut output).unwrap();
+    let reloaded = spine::SpineDocument::new(BufReader::new(&output[..])).unwrap();
+
+    assert!(reloaded.get_possible_sprites() == doc.get_possible_sprites());
+    assert!(reloaded.get_animation_duration("jump") == doc.get_animation_duration("jump"));
+
+    let a = doc.calculate("default", Some("walk"), 0.3).unwrap();
+    let b = reloaded.calculate("default", Some("walk"), 0.3).unwrap();
+    assert!(a.sprites.len() == b.sprites.len());
+    for (a, b) in a.sprites.iter().zip(b.sprites.iter()) {
+        assert!(a.name == b.name);
+        assert!((a.matrix.w.x - b.matrix.w.x).abs() < 0.001 &&
+                (a.matrix.w.y - b.matrix.w.y).abs() < 0.001);
+    }
+}
+
+#[test]
+fn draw_order() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [
+            { "name": "a", "bone": "root", "attachment": "a" },
+            { "name": "b", "bone": "root", "attachment": "b" },
+            { "name": "c", "bone": "root", "attachment": "c" }
+        ],
+        "skins": {
+            "default": {
+                "a": { "a": { "width": 10, "height": 10 } },
+                "b": { "b": { "width": 10, "height": 10 } },
+                "c": { "c": { "width": 10, "height": 10 } }
+            }
+        },
+        "animations": {
+            "shuffle": {
+                "draworder": [
+                    { "time": 0.5, "offsets": [ { "slot": "a", "offset": 2 } ] },
+                    { "time": 1 }
+                ]
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    assert!(doc.get_slots_list() == ["a", "b", "c"]);
+
+    let names = |elapsed| doc.calculate("default", Some("shuffle"), elapsed).unwrap()
+                             .sprites.iter().map(|s| s.name).collect::<Vec<_>>();
+    assert!(names(0.25) == ["a", "b", "c"]);
+    assert!(names(0.75) == ["b", "c", "a"]);
+
+    let orders = doc.get_animation_draw_orders("shuffle").unwrap();
+    assert!(orders == [(0.0, vec![0, 1, 2]), (0.5, vec![1, 2, 0]), (1.0, vec![0, 1, 2])]);
+    assert!(doc.get_animation_draw_orders("run").is_err());
+}
+
+#[test]
+fn fired_events() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "events": { "step": { "int": 1 }, "end": {} },
+        "animations": {
+            "walk": {
+                "events": [
+                    { "time": 0.5, "name": "step", "int": 2 },
+                    { "time": 1, "name": "end" }
+                ]
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let events = doc.get_fired_events("walk", 0.4, 0.6).unwrap();
+    assert!(events.len() == 1);
+    assert!(events[0].name == "step" && events[0].int == 2);
+
+    let events = doc.get_fired_events("walk", 0.9, 1.0).unwrap();
+    assert!(events.len() == 1 && events[0].name == "end");
+    assert!(doc.get_fired_events("walk", 1.0, 1.1).unwrap().len() == 0);
+
+    let names = doc.get_fired_events("walk", 0.0, 2.0).unwrap().iter()
+                   .map(|e| e.name).collect::<Vec<_>>();
+    assert!(names == ["step", "end", "step", "end"]);
+
+    assert!(doc.get_fired_events("run", 0.0, 1.0).is_none());
+
+    assert!(doc.get_fired_events("walk", std::f32::NAN, 1.0).is_none());
+    assert!(doc.get_fired_events("walk", 0.0, std::f32::INFINITY).is_none());
+    assert!(doc.get_fired_events("walk", 0.0, 1.0e9).is_none());
+    // past 2^24 loops, an f32 loop index stops increasing
+    assert!(doc.get_fired_events("walk", 3.0e7, 3.0e7 + 8.0).is_some());
+}
+
+#[test]
+fn image_path() {
+    let src: &[u8] = br#"{
+        "skeleton": { "images": "./images/" },
+        "bones": [ { "name": "root" } ]
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    assert!(doc.get_images_path() == Some("./images/"));
+    assert!(doc.get_image_path("eyes/open") == std::path::PathBuf::from("./images/eyes/open.png"));
+
+    let src: &[u8] = include_bytes!("example.json");
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+    assert!(doc.get_image_path("head") == std::path::PathBuf::from("head.png"));
+}
+
+#[test]
+fn sprite_attachment_type() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [
+            { "name": "body", "bone": "root", "attachment": "body" },
+            { "name": "hitbox", "bone": "root", "attachment": "hitbox" }
+        ],
+        "skins": {
+            "default": {
+                "body": { "body": { "width": 10, "height": 10 } },
+                "hitbox": { "hitbox": { "type": "boundingbox" } }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let results = doc.calculate("default", None, 0.0).unwrap();
+    assert!(results.sprites[0].attachment_type == spine::AttachmentType::Region);
+    assert!(results.sprites[1].attachment_type == spine::AttachmentType::BoundingBox);
+}
+
+#[test]
+fn color_formats() {
+    let doc = |color: &str| {
+        let src = format!(r#"{{
+            "bones": [ {{ "name": "root" }} ],
+            "slots": [ {{ "name": "slot", "bone": "root", "color": "{}", "attachment": "image" }} ],
+            "skins": {{ "default": {{ "slot": {{ "image": {{ "width": 10, "height": 10 }} }} }} }}
+        }}"#, color);
+        spine::SpineDocument::new(BufReader::new(src.as_bytes())).unwrap()
+    };
+
+    let eight = doc("10203040");
+    let results = eight.calculate("default", None, 0.0).unwrap();
+    let color = &results.sprites[0].color;
+    assert!(color.c.r == 0x10 && color.c.g == 0x20 && color.c.b == 0x30 && color.a == 0x40);
+
+    let six = doc("102030");
+    let results = six.calculate("default", None, 0.0).unwrap();
+    let color = &results.sprites[0].color;
+    assert!(color.c.r == 0x10 && color.c.g == 0x20 && color.c.b == 0x30 && color.a == 0xFF);
+
+    let invalid = doc("1020");
+    assert!(invalid.calculate("default", None, 0.0).is_err());
+
+    // hand-edited files can use any case
+    for &color in ["aabbccdd", "AABBCCDD", "aAbBcCdD"].iter() {
+        let doc = doc(color);
+        let results = doc.calculate("default", None, 0.0).unwrap();
+        let color = &results.sprites[0].color;
+        assert!(color.c.r == 0xAA && color.c.g == 0xBB && color.c.b == 0xCC && color.a == 0xDD);
+    }
+}
+
+#[test]
+fn color_timeline_case() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [ { "name": "slot", "bone": "root", "attachment": "image" } ],
+        "skins": { "default": { "slot": { "image": { "width": 10, "height": 10 } } } },
+        "animations": {
+            "lower": { "slots": { "slot": { "color": [ { "time": 0, "color": "aabbccdd" } ] } } },
+            "mixed": { "slots": { "slot": { "color": [ { "time": 0, "color": "AabBCcdD" } ] } } }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    for &animation in ["lower", "mixed"].iter() {
+        let results = doc.calculate("default", Some(animation), 0.0).unwrap();
+        let color = &results.sprites[0].color;
+        assert!(color.c.r == 0xAA && color.c.g == 0xBB && color.c.b == 0xCC && color.a == 0xDD);
+    }
+}
+
+#[test]
+fn calculate_frames() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [ { "name": "slot", "bone": "root", "attachment": "image" } ],
+        "skins": { "default": { "slot": { "image": { "width": 10, "height": 10 } } } },
+        "animations": {
+            "move": {
+                "bones": {
+                    "root": {
+                        "translate": [
+                            { "time": 0, "x": 0, "y": 0 },
+                            { "time": 1, "x": 10, "y": 0 }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    assert!(doc.calculate_frames("default", "move", 0).unwrap().len() == 0);
+
+    let single = doc.calculate_frames("default", "move", 1).unwrap();
+    assert!(single.len() == 1);
+    assert!(single[0].sprites[0].matrix.w.x.abs() < 0.001);
+
+    let frames = doc.calculate_frames("default", "move", 3).unwrap();
+    assert!(frames.len() == 3);
+    assert!(frames[0].sprites[0].matrix.w.x.abs() < 0.001);
+    assert!((frames[1].sprites[0].matrix.w.x - 5.0).abs() < 0.001);
+    assert!((frames[2].sprites[0].matrix.w.x - 10.0).abs() < 0.001);
+
+    assert!(doc.calculate_frames("default", "jump", 3).is_err());
+}
+
+#[test]
+fn calculate_at_keyframe() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [ { "name": "slot", "bone": "root", "attachment": "image" } ],
+        "skins": { "default": { "slot": { "image": { "width": 10, "height": 10 } } } },
+        "animations": {
+            "move": {
+                "bones": {
+                    "root": {
+                        "translate": [
+                            { "time": 0, "x": 0, "y": 0 },
+                            { "time": 0.3333, "x": 5, "y": 0 },
+                            { "time": 1, "x": 10, "y": 0 }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let second = doc.calculate_at_keyframe("default", "move", 1).unwrap().unwrap();
+    assert!((second.sprites[0].matrix.w.x - 5.0).abs() < 0.001);
+
+    // the last keyframe isn't wrapped to the start
+    let last = doc.calculate_at_keyframe("default", "move", 2).unwrap().unwrap();
+    assert!((last.sprites[0].matrix.w.x - 10.0).abs() < 0.001);
+
+    assert!(doc.calculate_at_keyframe("default", "move", 3).unwrap().is_none());
+    assert!(doc.calculate_at_keyframe("default", "crawl", 0).is_err());
+}
+
+#[test]
+fn frames_at_fps() {
+    let src: &[u8] = br#"{
+        "skeleton": { "fps": 4 },
+        "bones": [ { "name": "root" } ],
+        "slots": [ { "name": "slot", "bone": "root", "attachment": "image" } ],
+        "skins": { "default": { "slot": { "image": { "width": 10, "height": 10 } } } },
+        "animations": {
+            "move": {
+                "bones": {
+                    "root": {
+                        "translate": [
+                            { "time": 0, "x": 0, "y": 0 },
+                            { "time": 1, "x": 8, "y": 0 }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+    assert!(doc.get_fps() == Some(4.0));
+
+    let frames = doc.calculate_frames_at_fps("default", "move", 3).unwrap();
+    assert!(frames.len() == 3);
+    assert!((frames[1].sprites[0].matrix.w.x - 2.0).abs() < 0.001);
+    assert!((frames[2].sprites[0].matrix.w.x - 4.0).abs() < 0.001);
+}
+
+#[test]
+fn distinct_frames() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [ { "name": "slot", "bone": "root", "attachment": "image" } ],
+        "skins": { "default": { "slot": { "image": { "width": 10, "height": 10 } } } },
+        "animations": {
+            "hop": {
+                "bones": {
+                    "root": {
+                        "translate": [
+                            { "time": 0, "x": 0, "y": 0 },
+                            { "time": 0.5, "x": 0, "y": 0 },
+                            { "time": 1, "x": 10, "y": 0 }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let frames = doc.calculate_distinct_frames("default", "hop", 5, 0.001).unwrap();
+    let times: Vec<f32> = frames.iter().map(|&(time, _)| time).collect();
+    assert!(times == [0.0, 0.75, 1.0]);
+    assert!((frames[1].1.sprites[0].matrix.w.x - 5.0).abs() < 0.001);
+}
+
+#[test]
+fn baked_root_transform() {
+    let src: &[u8] = include_bytes!("example.json");
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let mut options = spine::CalculationOptions::default();
+    options.root_transform = Some(
+        cgmath::Matrix4::from_translation(&cgmath::Vector3::new(100.0, 50.0, 0.0)) *
+        cgmath::Matrix4::new(2.0, 0.0, 0.0, 0.0, 0.0, 2.0, 0.0, 0.0,
+                             0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0));
+
+    let duration = doc.get_animation_duration("walk").unwrap();
+    let frames = doc.calculate_frames_with_options("default", "walk", 3, &options).unwrap();
+    let live = doc.calculate_with_options("default", Some("walk"), duration / 2.0, &options)
+                  .unwrap();
+    assert!(frames[1].abs_diff_eq(&live, 0.001));
+
+    let normal = doc.calculate("default", Some("walk"), duration / 2.0).unwrap();
+    let (a, b) = (&live.sprites[0].matrix, &normal.sprites[0].matrix);
+    assert!((a.w.x - (b.w.x * 2.0 + 100.0)).abs() < 0.001);
+    assert!((a.w.y - (b.w.y * 2.0 + 50.0)).abs() < 0.001);
+}
+
+#[test]
+fn animation_state() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [ { "name": "slot", "bone": "root", "attachment": "image" } ],
+        "skins": { "default": { "slot": { "image": { "width": 10, "height": 10 } } } },
+        "animations": {
+            "move": {
+                "bones": {
+                    "root": {
+                        "translate": [
+                            { "time": 0, "x": 0, "y": 0 },
+                            { "time": 1, "x": 10, "y": 0 }
+                        ]
+                    }
+                }
+            },
+            "stay": {
+                "bones": {
+                    "root": {
+                        "translate": [
+                            { "time": 0, "x": 20, "y": 0 },
+                            { "time": 1, "x": 20, "y": 0 }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let mut state = spine::AnimationState::new();
+    assert!(state.get_current_animation(0).is_none());
+
+    state.add_animation(0, "move", 0.0);
+    state.add_animation(0, "stay", 0.5);
+    assert!(state.get_current_animation(0) == Some("move"));
+
+    state.update(&doc, 0.5);
+    let results = state.calculate(&doc, "default").unwrap();
+    assert!((results.sprites[0].matrix.w.x - 5.0).abs() < 0.001);
+
+    // the crossfade from the last frame of "move" starts when "stay" does
+    state.update(&doc, 0.75);
+    assert!(state.get_current_animation(0) == Some("stay"));
+    assert!((state.get_current_time(0) - 0.25).abs() < 0.001);
+    let results = state.calculate(&doc, "default").unwrap();
+    assert!((results.sprites[0].matrix.w.x - 10.0).abs() < 0.001);
+
+    state.update(&doc, 0.25);
+    let results = state.calculate(&doc, "default").unwrap();
+    assert!((results.sprites[0].matrix.w.x - 15.0).abs() < 0.001);
+
+    state.update(&doc, 0.5);
+    let results = state.calculate(&doc, "default").unwrap();
+    assert!((results.sprites[0].matrix.w.x - 20.0).abs() < 0.001);
+}
+
+#[test]
+fn animation_state_queue_after_loop() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "animations": {
+            "idle": {
+                "bones": {
+                    "root": {
+                        "translate": [
+                            { "time": 0, "x": 0, "y": 0 },
+                            { "time": 1, "x": 10, "y": 0 }
+                        ]
+                    }
+                }
+            },
+            "run": {
+                "bones": {
+                    "root": {
+                        "translate": [
+                            { "time": 0, "x": 20, "y": 0 },
+                            { "time": 1, "x": 20, "y": 0 }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let mut state = spine::AnimationState::new();
+    state.set_animation(0, "idle", 0.0);
+    state.update(&doc, 2.5);
+    state.add_animation(0, "run", 0.0);
+
+    // "idle" finishes its third loop first
+    state.update(&doc, 0.1);
+    assert!(state.get_current_animation(0) == Some("idle"));
+    assert!((state.get_current_time(0) - 2.6).abs() < 0.001);
+
+    state.update(&doc, 0.5);
+    assert!(state.get_current_animation(0) == Some("run"));
+    assert!((state.get_current_time(0) - 0.1).abs() < 0.001);
+}
+
+#[test]
+fn animation_state_mix_to_setup() {
+    let src: &[u8] = br#"{
+        "bones": [
+            { "name": "root" },
+            { "name": "arm", "parent": "root" },
+            { "name": "leg", "parent": "root" }
+        ],
+        "slots": [ { "name": "arm", "bone": "arm", "attachment": "image" } ],
+        "skins": { "default": { "arm": { "image": { "width": 10, "height": 10 } } } },
+        "animations": {
+            "wave": {
+                "bones": {
+                    "arm": {
+                        "translate": [
+                            { "time": 0, "x": 0, "y": 10 },
+                            { "time": 1, "x": 0, "y": 10 }
+                        ]
+                    }
+                },
+                "slots": {
+                    "arm": {
+                        "color": [
+                            { "time": 0, "color": "00000000" },
+                            { "time": 1, "color": "00000000" }
+                        ]
+                    }
+                }
+            },
+            "kick": {
+                "bones": {
+                    "leg": {
+                        "translate": [
+                            { "time": 0, "x": 5, "y": 0 },
+                            { "time": 1, "x": 5, "y": 0 }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let mut state = spine::AnimationState::new();
+    state.set_animation(0, "wave", 0.0);
+    state.update(&doc, 0.5);
+    state.set_animation(0, "kick", 0.5);
+
+    // the arm isn't animated by "kick" and is faded out towards the setup pose
+    state.update(&doc, 0.25);
+    let results = state.calculate(&doc, "default").unwrap();
+    assert!((results.sprites[0].matrix.w.y - 5.0).abs() < 0.001);
+    assert!(results.sprites[0].color.a == 128);
+
+    state.update(&doc, 0.5);
+    let results = state.calculate(&doc, "default").unwrap();
+    assert!(results.sprites[0].matrix.w.y.abs() < 0.001);
+    assert!(results.sprites[0].color.a == 255);
+}
+
+#[test]
+fn animation_state_tracks_show_slot() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [ { "name": "flash", "bone": "root" } ],
+        "skins": { "default": { "flash": { "image": { "width": 10, "height": 10 } } } },
+        "animations": {
+            "fade": {
+                "slots": {
+                    "flash": {
+                        "color": [
+                            { "time": 0, "color": "ff000080" },
+                            { "time": 1, "color": "ff000080" }
+                        ]
+                    }
+                }
+            },
+            "show": {
+                "slots": {
+                    "flash": {
+                        "attachment": [ { "time": 0, "name": "image" } ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let mut state = spine::AnimationState::new();
+    state.set_animation(0, "fade", 0.0);
+    state.set_animation(1, "show", 0.0);
+
+    // the slot is only made visible by the upper track, but keeps the color of the lower one
+    state.update(&doc, 0.5);
+    let results = state.calculate(&doc, "default").unwrap();
+    assert!(results.sprites.len() == 1);
+    assert!(results.sprites[0].color.c.g == 0);
+    assert!(results.sprites[0].color.a == 128);
+}
+
+#[test]
+fn animation_state_tracks() {
+    let src: &[u8] = br#"{
+        "bones": [
+            { "name": "root" },
+            { "name": "arm", "parent": "root" }
+        ],
+        "slots": [ { "name": "slot", "bone": "arm", "attachment": "image" } ],
+        "skins": { "default": { "slot": { "image": { "width": 10, "height": 10 } } } },
+        "animations": {
+            "walk": {
+                "bones": {
+                    "root": {
+                        "translate": [
+                            { "time": 0, "x": 10, "y": 0 },
+                            { "time": 1, "x": 10, "y": 0 }
+                        ]
+                    },
+                    "arm": {
+                        "translate": [
+                            { "time": 0, "x": 0, "y": 0 },
+                            { "time": 1, "x": 0, "y": 0 }
+                        ]
+                    }
+                }
+            },
+            "shoot": {
+                "bones": {
+                    "arm": {
+                        "translate": [
+                            { "time": 0, "x": 0, "y": 20 },
+                            { "time": 1, "x": 0, "y": 20 }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let mut state = spine::AnimationState::new();
+    state.set_animation(0, "walk", 0.0);
+    state.set_animation(1, "shoot", 0.0);
+    assert!(state.get_current_animation(1) == Some("shoot"));
+
+    // the root bone is left to the lower track
+    state.update(&doc, 0.5);
+    let results = state.calculate(&doc, "default").unwrap();
+    assert!((results.sprites[0].matrix.w.x - 10.0).abs() < 0.001);
+    assert!((results.sprites[0].matrix.w.y - 20.0).abs() < 0.001);
+
+    state.set_track_alpha(1, 0.5);
+    let results = state.calculate(&doc, "default").unwrap();
+    assert!((results.sprites[0].matrix.w.x - 10.0).abs() < 0.001);
+    assert!((results.sprites[0].matrix.w.y - 10.0).abs() < 0.001);
+
+    state.clear_track(1);
+    assert!(state.get_current_animation(1).is_none());
+    let results = state.calculate(&doc, "default").unwrap();
+    assert!(results.sprites[0].matrix.w.y.abs() < 0.001);
+}
+
+#[test]
+#[cfg(feature = "cache")]
+fn cache() {
+    let src: &[u8] = include_bytes!("example.json");
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let mut cache = Vec::new();
+    doc.write_cache(&mut cache).unwrap();
+    let loaded = spine::SpineDocument::read_cache(&cache[..]).unwrap();
+    assert!(loaded.get_content_hash() == doc.get_content_hash());
+
+    cache[0] = b'x';
+    match spine::SpineDocument::read_cache(&cache[..]) {
+        Err(spine::LoadError::InvalidCache) => (),
+        _ => panic!()
+    };
+}
+
+#[test]
+fn document_clone() {
+    let src: &[u8] = include_bytes!("example.json");
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+    let copy = doc.clone();
+
+    let original = doc.calculate("default", Some("walk"), 0.4).unwrap();
+    let cloned = copy.calculate("default", Some("walk"), 0.4).unwrap();
+    assert!(original.to_pose() == cloned.to_pose());
+}
+
+#[test]
+fn bezier_curve_is_continuous() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "animations": {
+            "ease": {
+                "bones": {
+                    "root": {
+                        "translate": [
+                            { "time": 0, "x": 0, "y": 0, "curve": [ 0.25, 0.1, 0.25, 1 ] },
+                            { "time": 1, "x": 100, "y": 0 }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let mut previous = 0.0;
+    for step in 0 .. 1000 {
+        let bones = doc.calculate_bones(Some("ease"), step as f32 / 1000.0).unwrap();
+        let x = bones[0].1.w.x;
+        assert!(x >= previous - 0.001);
+        assert!(x - previous < 1.0);
+        previous = x;
+    }
+}
+
+#[test]
+fn custom_curve_function() {
+    fn ease_in(position: f32) -> f32 { position * position }
+
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "animations": {
+            "move": {
+                "bones": {
+                    "root": {
+                        "translate": [
+                            { "time": 0, "x": 0, "y": 0, "curve": "easeIn" },
+                            { "time": 1, "x": 10, "y": 0 }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    assert!(doc.calculate_bones(Some("move"), 0.5).is_err());
+
+    let mut options = spine::CalculationOptions::default();
+    options.curve_functions.insert("easeIn".to_string(), ease_in as fn(f32) -> f32);
+
+    let bones = doc.calculate_bones_with_options(Some("move"), 0.5, &options).unwrap();
+    assert!((bones[0].1.w.x - 2.5).abs() < 0.001);
+}
+
+#[test]
+fn timelines_sampling() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "animations": {
+            "move": {
+                "bones": {
+                    "root": {
+                        "translate": [
+                            { "time": 0, "x": 0, "y": 0 },
+                            { "time": 0.5, "x": 10, "y": 0 }
+                        ],
+                        "rotate": [
+                            { "time": 0.2, "angle": 0 },
+                            { "time": 1, "angle": 90 }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let sampling = doc.get_timelines_sampling("move", 0.1).unwrap();
+    assert!(sampling.len() == 2);
+    assert!(sampling[0].timeline == "rotate");
+    assert!(sampling[0].position == spine::SamplingPosition::BeforeFirstKeyframe);
+    assert!(sampling[1].timeline == "translate");
+    assert!(sampling[1].position == spine::SamplingPosition::Interpolated);
+
+    let sampling = doc.get_timelines_sampling("move", 0.75).unwrap();
+    assert!(sampling[0].position == spine::SamplingPosition::Interpolated);
+    assert!(sampling[1].position == spine::SamplingPosition::AfterLastKeyframe);
+
+    assert!(doc.get_timelines_sampling("jump", 0.0).is_none());
+}
+
+#[test]
+fn reflected_parent() {
+    let src: &[u8] = br#"{
+        "bones": [
+            { "name": "root", "scaleX": -1 },
+            { "name": "child", "parent": "root", "x": 10, "rotation": 90 }
+        ]
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    // the child is mirrored by its parent, including its rotation
+    let bones = doc.calculate_bones(None, 0.0).unwrap();
+    let child = &bones[1].1;
+    assert!((child.w.x + 10.0).abs() < 0.001 && child.w.y.abs() < 0.001);
+    assert!(child.x.x.abs() < 0.001 && (child.x.y - 1.0).abs() < 0.001);
+    assert!((child.y.x - 1.0).abs() < 0.001 && child.y.y.abs() < 0.001);
+}
+
+#[test]
+fn attachments_list() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [ { "name": "slot", "bone": "root", "attachment": "setup" } ],
+        "skins": {
+            "default": { "slot": { "setup": {}, "open": {} } },
+            "armored": { "slot": { "helmet": {} } }
+        },
+        "animations": {
+            "blink": {
+                "slots": {
+                    "slot": {
+                        "attachment": [
+                            { "time": 0, "name": "closed" },
+                            { "time": 0.5, "name": null }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    assert!(doc.get_attachments_list() == vec!["closed", "helmet", "open", "setup"]);
+}
+
+#[test]
+fn animation_range() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "animations": {
+            "move": {
+                "bones": {
+                    "root": {
+                        "translate": [
+                            { "time": 0, "x": 0, "y": 0 },
+                            { "time": 1, "x": 10, "y": 0 }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let mut options = spine::CalculationOptions::default();
+    options.range = Some((0.5, 1.0));
+
+    let bones = doc.calculate_bones_with_options(Some("move"), 0.25, &options).unwrap();
+    assert!((bones[0].1.w.x - 7.5).abs() < 0.001);
+
+    // looping inside of the range
+    let bones = doc.calculate_bones_with_options(Some("move"), 0.75, &options).unwrap();
+    assert!((bones[0].1.w.x - 7.5).abs() < 0.001);
+
+    options.range = Some((2.0, 3.0));
+    match doc.calculate_bones_with_options(Some("move"), 0.0, &options) {
+        Err(spine::CalculationError::InvalidRange) => (),
+        _ => panic!()
+    };
+}
+
+#[test]
+fn content_hash() {
+    let first: &[u8] = br#"{
+        "skeleton": { "hash": "abc" },
+        "bones": [ { "name": "root" }, { "name": "child", "parent": "root", "x": 5 } ],
+        "skins": { "default": { "slot": { "a": {}, "b": {} } } }
+    }"#;
+    let reordered: &[u8] = br#"{
+        "skins": { "default": { "slot": { "b": {}, "a": {} } } },
+        "bones": [ { "name": "root" }, { "x": 5, "parent": "root", "name": "child" } ],
+        "skeleton": { "hash": "abc" }
+    }"#;
+    let modified: &[u8] = br#"{
+        "skeleton": { "hash": "abc" },
+        "bones": [ { "name": "root" }, { "name": "child", "parent": "root", "x": 6 } ],
+        "skins": { "default": { "slot": { "a": {}, "b": {} } } }
+    }"#;
+
+    let first = spine::SpineDocument::new(BufReader::new(first)).unwrap();
+    let reordered = spine::SpineDocument::new(BufReader::new(reordered)).unwrap();
+    let modified = spine::SpineDocument::new(BufReader::new(modified)).unwrap();
+
+    assert!(first.get_content_hash() == reordered.get_content_hash());
+    assert!(first.get_content_hash() != modified.get_content_hash());
+}
+
+#[test]
+fn calculate_colors() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [
+            { "name": "bar", "bone": "root", "color": "FF000080" },
+            { "name": "background", "bone": "root" }
+        ],
+        "animations": {
+            "fade": {
+                "slots": {
+                    "bar": {
+                        "color": [
+                            { "time": 0, "color": "00000000" },
+                            { "time": 1, "color": "0A0A0A0A" }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let colors = doc.calculate_colors(None, 0.0).unwrap();
+    assert!(colors.len() == 2);
+    assert!(colors[0].0 == "bar" && colors[0].1.c.r == 0xFF && colors[0].1.a == 0x80);
+    assert!(colors[1].0 == "background" && colors[1].1.a == 0xFF);
+
+    let colors = doc.calculate_colors(Some("fade"), 0.5).unwrap();
+    assert!(colors[0].1.c.r == 5 && colors[0].1.a == 5);
+    assert!(colors[1].1.c.r == 0xFF);
+}
+
+#[test]
+fn attachment_types() {
+    let doc = |type_: &str| {
+        let src = format!(r#"{{
+            "bones": [ {{ "name": "root" }} ],
+            "slots": [ {{ "name": "slot", "bone": "root", "attachment": "image" }} ],
+            "skins": {{ "default": {{ "slot": {{
+                "image": {{ "type": "{}", "parent": "source" }},
+                "source": {{ "type": "mesh" }}
+            }} }} }}
+        }}"#, type_);
+        spine::SpineDocument::new(BufReader::new(src.as_bytes()))
+    };
+
+    let types = [("mesh", spine::AttachmentType::Mesh),
+                 ("linkedmesh", spine::AttachmentType::LinkedMesh),
+                 ("path", spine::AttachmentType::Path),
+                 ("point", spine::AttachmentType::Point),
+                 ("clipping", spine::AttachmentType::Clipping)];
+
+    for &(name, ref type_) in types.iter() {
+        let doc = doc(name).unwrap();
+        let results = doc.calculate("default", None, 0.0).unwrap();
+        assert!(results.sprites[0].attachment_type == *type_);
+    }
+
+    assert!(doc("skinnedmesh2").is_err());
+}
+
+#[test]
+fn region_sequence_mode() {
+    let doc = |mode: &str| {
+        let src = format!(r#"{{
+            "bones": [ {{ "name": "root" }} ],
+            "slots": [ {{ "name": "slot", "bone": "root", "attachment": "image" }} ],
+            "skins": {{ "default": {{ "slot": {{
+                "image": {{ "type": "regionsequence", "fps": 10, "mode": {} }}
+            }} }} }}
+        }}"#, mode);
+        spine::SpineDocument::new(BufReader::new(src.as_bytes()))
+    };
+
+    for mode in ["0", "2", "5"].iter() {
+        assert!(doc(mode).is_ok());
+    }
+
+    match doc("6") {
+        Err(spine::LoadError::Format(_)) => (),
+        _ => panic!()
+    }
+}
+
+#[test]
+fn random_sequence() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [ { "name": "fire", "bone": "root", "attachment": "flame" } ],
+        "skins": {
+            "default": {
+                "fire": {
+                    "flame": {
+                        "path": "flame/", "width": 10, "height": 10,
+                        "sequence": { "count": 3, "digits": 2 }
+                    }
+                }
+            }
+        },
+        "animations": {
+            "burn": {
+                "attachments": {
+                    "default": {
+                        "fire": {
+                            "flame": {
+                                "sequence": [
+                                    { "time": 0, "mode": "random", "delay": 0.1 },
+                                    { "time": 1 }
+                                ]
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let mut options = spine::CalculationOptions::default();
+    options.random_seed = 42;
+
+    for step in 0 .. 10 {
+        let elapsed = step as f32 * 0.1 + 0.05;
+        let first = doc.calculate_with_options("default", Some("burn"), elapsed, &options)
+                       .unwrap().to_pose();
+        let second = doc.calculate_with_options("default", Some("burn"), elapsed, &options)
+                        .unwrap().to_pose();
+
+        assert!(first == second);
+        assert!(doc.get_possible_sprites().contains(&&first.sprites[0].name[..]));
+    }
+}
+
+#[test]
+fn path_point() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root", "x": 5 } ],
+        "slots": [
+            { "name": "road", "bone": "root", "attachment": "road" },
+            { "name": "image", "bone": "root", "attachment": "image" }
+        ],
+        "skins": {
+            "default": {
+                "road": {
+                    "road": {
+                        "type": "path", "lengths": [ 30 ], "vertexCount": 6,
+                        "vertices": [ -10, 0, 0, 0, 10, 0, 20, 0, 30, 0, 40, 0 ]
+                    }
+                },
+                "image": { "image": { "width": 10, "height": 10 } }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let point = doc.calculate_path_point("default", None, 0.0, "road", "road", 0.5)
+                   .unwrap().unwrap();
+    assert!((point.position.0 - 20.0).abs() < 0.001 && point.position.1.abs() < 0.001);
+    assert!((point.tangent.0 - 1.0).abs() < 0.001 && point.tangent.1.abs() < 0.001);
+
+    // open paths are clamped
+    let point = doc.calculate_path_point("default", None, 0.0, "road", "road", 2.0)
+                   .unwrap().unwrap();
+    assert!((point.position.0 - 35.0).abs() < 0.001);
+
+    assert!(doc.calculate_path_point("default", None, 0.0, "image", "image", 0.5)
+               .unwrap().is_none());
+    assert!(doc.calculate_path_point("default", None, 0.0, "road", "river", 0.5).is_err());
+}
+
+#[test]
+fn animation_events() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "events": { "step": { "int": 1 }, "end": {} },
+        "animations": {
+            "walk": {
+                "events": [
+                    { "time": 1, "name": "end" },
+                    { "time": 0.5, "name": "step", "int": 2 },
+                    { "time": 0.2, "name": "step" }
+                ]
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let events = doc.get_animation_events("walk").unwrap();
+    assert!(events.len() == 3);
+    assert!(events[0].0 == 0.2 && events[0].1.name == "step" && events[0].1.int == 1);
+    assert!(events[1].0 == 0.5 && events[1].1.int == 2);
+    assert!(events[2].0 == 1.0 && events[2].1.name == "end");
+
+    assert!(doc.get_animation_events("run").is_none());
+}
+
+#[test]
+fn trailing_hidden_attachment() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [ { "name": "slot", "bone": "root", "attachment": "image" } ],
+        "skins": { "default": { "slot": { "image": { "width": 10, "height": 10 } } } },
+        "animations": {
+            "vanish": {
+                "bones": {
+                    "root": { "translate": [ { "time": 0 }, { "time": 1 } ] }
+                },
+                "slots": {
+                    "slot": {
+                        "attachment": [
+                            { "time": 0, "name": "image" },
+                            { "time": 0.5, "name": null }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    assert!(doc.calculate("default", Some("vanish"), 0.25).unwrap().sprites.len() == 1);
+    assert!(doc.calculate("default", Some("vanish"), 0.75).unwrap().sprites.len() == 0);
+}
+
+#[test]
+fn slot_attachment() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [ { "name": "shield", "bone": "root", "attachment": "lowered" } ],
+        "skins": { "default": { "shield": {
+            "lowered": { "width": 10, "height": 10 },
+            "raised": { "width": 10, "height": 10 }
+        } } },
+        "animations": {
+            "block": {
+                "slots": {
+                    "shield": {
+                        "attachment": [
+                            { "time": 0.5, "name": "raised" },
+                            { "time": 1, "name": null }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    assert!(doc.get_slot_attachment(None, "shield", 0.0).unwrap() == Some("lowered"));
+    assert!(doc.get_slot_attachment(Some("block"), "shield", 0.25).unwrap() == Some("lowered"));
+    assert!(doc.get_slot_attachment(Some("block"), "shield", 0.75).unwrap() == Some("raised"));
+    assert!(doc.get_slot_attachment(Some("block"), "shield", 1.0).unwrap() == Some("lowered"));
+    assert!(doc.get_slot_attachment(Some("block"), "sword", 0.75).unwrap().is_none());
+    assert!(doc.get_slot_attachment(Some("parry"), "shield", 0.75).is_err());
+}
+
+#[test]
+fn linked_mesh() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [ { "name": "cape", "bone": "root", "attachment": "cape" } ],
+        "skins": {
+            "default": { "cape": { "cape": {
+                "type": "mesh", "width": 10, "height": 10, "hull": 3,
+                "uvs": [ 0, 0, 1, 0, 0, 1 ],
+                "triangles": [ 0, 1, 2 ],
+                "vertices": [ 0, 0, 10, 0, 0, 10 ]
+            } } },
+            "red": { "cape": { "cape": {
+                "type": "linkedmesh", "path": "red-cape", "parent": "cape", "skin": "default"
+            } } }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+    assert!(doc.get_attachment_size("red", "cape", "cape") == Some((10.0, 10.0)));
+    assert!(doc.get_max_attachment_vertices() == 3);
+
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [ { "name": "cape", "bone": "root" } ],
+        "skins": { "default": { "cape": { "red-cape": {
+            "type": "linkedmesh", "parent": "cape"
+        } } } }
+    }"#;
+    match spine::SpineDocument::new(BufReader::new(src)) {
+        Err(spine::LoadError::AttachmentNotFound(ref name)) if name == "cape" => (),
+        _ => panic!()
+    };
+}
+
+#[test]
+fn freeze_attachments() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [ { "name": "slot", "bone": "root", "attachment": "open" } ],
+        "skins": { "default": { "slot": {
+            "open": { "width": 10, "height": 10 },
+            "closed": { "width": 10, "height": 10 }
+        } } },
+        "animations": {
+            "blink": {
+                "bones": {
+                    "root": {
+                        "translate": [
+                            { "time": 0, "x": 0, "y": 0 },
+                            { "time": 1, "x": 10, "y": 0 }
+                        ]
+                    }
+                },
+                "slots": {
+                    "slot": {
+                        "attachment": [
+                            { "time": 0, "name": "closed" },
+                            { "time": 1, "name": "open" }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let results = doc.calculate("default", Some("blink"), 0.5).unwrap();
+    assert!(results.sprites[0].name == "closed");
+
+    let mut options = spine::CalculationOptions::default();
+    options.freeze_attachments = true;
+    let results = doc.calculate_with_options("default", Some("blink"), 0.5, &options).unwrap();
+    assert!(results.sprites[0].name == "open");
+    assert!((results.sprites[0].matrix.w.x - 5.0).abs() < 0.001);
+}
+
+#[test]
+fn attachment_overrides() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [
+            { "name": "hand", "bone": "root", "attachment": "fist" },
+            { "name": "head", "bone": "root", "attachment": "face" }
+        ],
+        "skins": {
+            "default": {
+                "hand": { "fist": { "width": 10, "height": 10 } },
+                "head": { "face": { "width": 10, "height": 10 } }
+            },
+            "knight": { "hand": { "sword": { "width": 10, "height": 40 } } }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let mut options = spine::CalculationOptions::default();
+    options.attachment_overrides.insert("hand".to_string(), Some("sword".to_string()));
+    options.attachment_overrides.insert("head".to_string(), None);
+
+    let results = doc.calculate_with_options("default", None, 0.0, &options).unwrap();
+    assert!(results.sprites.len() == 1);
+    assert!(results.sprites[0].name == "sword");
+
+    options.attachment_overrides.clear();
+    let results = doc.calculate_with_options("default", None, 0.0, &options).unwrap();
+    assert!(results.sprites.len() == 2);
+    assert!(results.sprites[0].name == "fist");
+}
+
+#[test]
+fn attachment_override_color() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [ { "name": "hand", "bone": "root" } ],
+        "skins": { "default": { "hand": { "sword": { "width": 10, "height": 40 } } } },
+        "animations": {
+            "glow": {
+                "slots": {
+                    "hand": {
+                        "color": [
+                            { "time": 0, "color": "FF000080" },
+                            { "time": 1, "color": "FF000080" }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let mut options = spine::CalculationOptions::default();
+    options.attachment_overrides.insert("hand".to_string(), Some("sword".to_string()));
+
+    // the slot has no attachment of its own, but the overridden one is animated
+    let results = doc.calculate_with_options("default", Some("glow"), 0.5, &options).unwrap();
+    assert!(results.sprites.len() == 1);
+    assert!(results.sprites[0].color.c.g == 0 && results.sprites[0].color.a == 0x80);
+}
+
+#[test]
+fn max_attachment_vertices() {
+    let src: &[u8] = include_bytes!("example.json");
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+    assert!(doc.get_max_attachment_vertices() == 4);
+
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [ { "name": "cape", "bone": "root" } ],
+        "skins": { "default": { "cape": { "cape": {
+            "type": "mesh", "uvs": [ 0, 0, 1, 0, 1, 1, 0, 1, 0.5, 0.5 ],
+            "vertices": [ 0, 0, 10, 0, 10, 10, 0, 10, 5, 5 ]
+        } } } }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+    assert!(doc.get_max_attachment_vertices() == 5);
+
+    let src: &[u8] = br#"{ "bones": [ { "name": "root" } ] }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+    assert!(doc.get_max_attachment_vertices() == 0);
+}
+
+#[test]
+fn scaled_document() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root", "x": 5, "rotation": 90 } ],
+        "animations": {
+            "move": {
+                "bones": {
+                    "root": {
+                        "translate": [
+                            { "time": 0, "x": 10, "y": 0 },
+                            { "time": 1, "x": 10, "y": 0 }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new_scaled(BufReader::new(src), 2.0).unwrap();
+
+    let bones = doc.calculate_bones(None, 0.0).unwrap();
+    assert!((bones[0].1.w.x - 10.0).abs() < 0.001);
+
+    // rotations are not scaled
+    assert!(bones[0].1.x.x.abs() < 0.001 && (bones[0].1.x.y - 1.0).abs() < 0.001);
+
+    let bones = doc.calculate_bones(Some("move"), 0.0).unwrap();
+    assert!((bones[0].1.w.x - 30.0).abs() < 0.001);
+}
+
+#[test]
+fn scaled_vertices() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [ { "name": "slot", "bone": "root" } ],
+        "skins": {
+            "default": {
+                "slot": {
+                    "box": { "type": "boundingbox", "vertices": [ 0, 0, 10, 0, 10, 5 ] },
+                    "mesh": {
+                        "type": "mesh", "uvs": [ 0, 0, 1, 1 ], "triangles": [],
+                        "vertices": [ 1, 3, 10, 20, 1, 2, 0, 5, 6, 0.5, 0, 7, 8, 0.5 ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new_scaled(BufReader::new(src), 2.0).unwrap();
+
+    let points = doc.calculate_bounding_box("default", None, 0.0, "slot", "box").unwrap()
+                    .unwrap();
+    assert!((points[2].0 - 20.0).abs() < 0.001 && (points[2].1 - 10.0).abs() < 0.001);
+
+    // the bone indices and the weights of the mesh are not scaled
+    let mut output = Vec::new();
+    doc.to_json_writer(&mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("[1.0,3.0,20.0,40.0,1.0,2.0,0.0,10.0,12.0,0.5,0.0,14.0,16.0,0.5]"));
+}
+
+#[test]
+fn attachment_size() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [ { "name": "slot", "bone": "root", "attachment": "image" } ],
+        "skins": {
+            "default": { "slot": { "image": { "width": 10, "height": 20 }, "point": {} } },
+            "large": { "slot": { "image": { "width": 30, "height": 40 } } }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new_scaled(BufReader::new(src), 2.0).unwrap();
+
+    assert!(doc.get_attachment_size("default", "slot", "image") == Some((10.0, 20.0)));
+    assert!(doc.get_attachment_size("large", "slot", "image") == Some((30.0, 40.0)));
+    assert!(doc.get_attachment_size("large", "slot", "point").is_none());
+    assert!(doc.get_attachment_size("default", "slot", "missing").is_none());
+
+    for &scale in [0.0, std::f32::NAN, std::f32::INFINITY].iter() {
+        match spine::SpineDocument::new_scaled(BufReader::new(src), scale) {
+            Err(spine::LoadError::InvalidScale) => (),
+            _ => panic!()
+        }
+    }
+}
+
+#[test]
+fn sprites_comparison() {
+    let src: &[u8] = include_bytes!("example.json");
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let first = doc.calculate("default", Some("walk"), 0.3).unwrap();
+    let second = doc.calculate("default", Some("walk"), 0.3).unwrap();
+    assert!(first == second);
+
+    let mut moved = second.clone();
+    moved.sprites[0].matrix.w.x += 0.0001;
+    assert!(first != moved);
+    assert!(first.abs_diff_eq(&moved, 0.001));
+    assert!(!first.abs_diff_eq(&moved, 0.00001));
+
+    let other = doc.calculate("default", Some("walk"), 0.5).unwrap();
+    assert!(!first.abs_diff_eq(&other, 0.001));
+}
+
+#[test]
+fn loop_start() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "animations": {
+            "reload": {
+                "bones": {
+                    "root": {
+                        "translate": [
+                            { "time": 0, "x": 0, "y": 0 },
+                            { "time": 1, "x": 10, "y": 0 }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let mut options = spine::CalculationOptions::default();
+    options.loop_start = Some(0.5);
+
+    // the intro is only played once
+    let bones = doc.calculate_bones_with_options(Some("reload"), 0.25, &options).unwrap();
+    assert!((bones[0].1.w.x - 2.5).abs() < 0.001);
+    let bones = doc.calculate_bones_with_options(Some("reload"), 1.25, &options).unwrap();
+    assert!((bones[0].1.w.x - 7.5).abs() < 0.001);
+    let bones = doc.calculate_bones_with_options(Some("reload"), 1.75, &options).unwrap();
+    assert!((bones[0].1.w.x - 7.5).abs() < 0.001);
+
+    options.loop_start = Some(1.0);
+    assert!(doc.calculate_bones_with_options(Some("reload"), 0.0, &options).is_err());
+}
+
+#[test]
+fn local_bones() {
+    let src: &[u8] = br#"{
+        "bones": [
+            { "name": "root", "x": 5 },
+            { "name": "child", "parent": "root", "x": 10 }
+        ]
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let local = doc.calculate_local_bones(None, 0.0).unwrap();
+    let world = doc.calculate_bones(None, 0.0).unwrap();
+
+    assert!(local[1].0 == "child" && world[1].0 == "child");
+    assert!((local[1].1.w.x - 10.0).abs() < 0.001);
+    assert!((world[1].1.w.x - 15.0).abs() < 0.001);
+}
+
+#[test]
+fn retarget_animation() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" }, { "name": "tail", "parent": "root" } ],
+        "animations": {
+            "wag": {
+                "bones": {
+                    "root": {
+                        "translate": [
+                            { "time": 0, "x": 10, "y": 0 },
+                            { "time": 1, "x": 10, "y": 0 }
+                        ]
+                    },
+                    "tail": { "rotate": [ { "time": 0, "angle": 0 }, { "time": 1, "angle": 45 } ] }
+                }
+            }
+        }
+    }"#;
+    let source = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let src: &[u8] = br#"{ "bones": [ { "name": "root", "x": 100 } ] }"#;
+    let mut doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let warnings = doc.retarget_animation(&source, "wag").unwrap();
+    assert!(warnings == ["bone `tail` not found"]);
+
+    let bones = doc.calculate_bones(Some("wag"), 0.5).unwrap();
+    assert!((bones[0].1.w.x - 110.0).abs() < 0.001);
+
+    assert!(doc.retarget_animation(&source, "run").is_none());
+}
+
+#[test]
+fn replace_animation_lenient() {
+    let src: &[u8] = include_bytes!("example.json");
+    let mut doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let anim: &[u8] = br#"{
+        "bones": {
+            "hip": { "rotate": [ { "time": 0, "angle": 0 }, { "time": 2, "angle": 90 } ] },
+            "tail": { "rotate": [ { "time": 3, "angle": 0 } ] }
+        }
+    }"#;
+    let warnings = doc.replace_animation_lenient("walk", BufReader::new(anim)).unwrap();
+    assert!(warnings == vec!["bone `tail` not found".to_string()]);
+
+    // the timeline of the missing bone is ignored
+    assert!(doc.get_animation_duration("walk") == Some(2.0));
+    assert!(doc.calculate("default", Some("walk"), 1.0).is_ok());
+}
+
+#[test]
+fn setup_overrides() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root", "x": 5 } ],
+        "slots": [ { "name": "body", "bone": "root", "attachment": "a" } ]
+    }"#;
+    let mut doc = spine::SpineDocument::new_scaled(BufReader::new(src), 2.0).unwrap();
+
+    let overrides: &[u8] = br#"{ "bones": { "root": { "y": 10 }, "tail": { "x": 1 } } }"#;
+    assert!(doc.apply_setup_overrides(BufReader::new(overrides), false).is_err());
+    assert!((doc.calculate_bones(None, 0.0).unwrap()[0].1.w.y - 0.0).abs() < 0.001);
+
+    let warnings = doc.apply_setup_overrides(BufReader::new(overrides), true).unwrap();
+    assert!(warnings == ["bone `tail` not found"]);
+    {
+        let bones = doc.calculate_bones(None, 0.0).unwrap();
+        assert!((bones[0].1.w.x - 10.0).abs() < 0.001);
+        assert!((bones[0].1.w.y - 20.0).abs() < 0.001);
+    }
+
+    let overrides: &[u8] = br#"{ "slots": { "body": { "attachment": "b" } } }"#;
+    assert!(doc.apply_setup_overrides(BufReader::new(overrides), false).unwrap().is_empty());
+    assert!(doc.get_slot_attachment(None, "body", 0.0).unwrap() == Some("b"));
+
+    // invalid colors are rejected even in lenient mode
+    let overrides: &[u8] = br#"{ "slots": { "body": { "color": "red" } } }"#;
+    match doc.apply_setup_overrides(BufReader::new(overrides), true) {
+        Err(spine::LoadError::Format(_)) => (),
+        _ => panic!()
+    }
+}
+
+#[test]
+fn empty_animation() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" }, { "name": "arm", "parent": "root" } ],
+        "slots": [ { "name": "arm", "bone": "arm", "attachment": "image" } ],
+        "skins": { "default": { "arm": { "image": { "width": 10, "height": 10 } } } },
+        "animations": {
+            "wave": {
+                "bones": {
+                    "arm": {
+                        "translate": [
+                            { "time": 0, "x": 0, "y": 10 },
+                            { "time": 1, "x": 0, "y": 10 }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+    assert!(doc.get_animation_duration(spine::EMPTY_ANIMATION) == Some(0.0));
+    let results = doc.calculate("default", Some(spine::EMPTY_ANIMATION), 0.0).unwrap();
+    assert!(results.sprites[0].matrix.w.y.abs() < 0.001);
+
+    let mut state = spine::AnimationState::new();
+    state.set_animation(0, "wave", 0.0);
+    state.add_empty_animation(0, 0.5);
+
+    state.update(&doc, 1.0);
+    assert!(state.get_current_animation(0) == Some(spine::EMPTY_ANIMATION));
+    state.update(&doc, 0.25);
+    let results = state.calculate(&doc, "default").unwrap();
+    assert!((results.sprites[0].matrix.w.y - 5.0).abs() < 0.001);
+
+    state.update(&doc, 0.5);
+    let results = state.calculate(&doc, "default").unwrap();
+    assert!(results.sprites[0].matrix.w.y.abs() < 0.001);
+}
+
+#[test]
+fn bone_inherit() {
+    let src: &[u8] = br#"{
+        "bones": [
+            { "name": "root" },
+            { "name": "arm", "parent": "root", "inheritScale": false }
+        ]
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    assert!(doc.get_bone_inherit("root") == Some((true, true)));
+    assert!(doc.get_bone_inherit("arm") == Some((false, true)));
+    assert!(doc.get_bone_inherit("leg").is_none());
+}
+
+#[test]
+fn for_each_frame() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [ { "name": "slot", "bone": "root", "attachment": "image" } ],
+        "skins": { "default": { "slot": { "image": { "width": 10, "height": 10 } } } },
+        "animations": {
+            "move": {
+                "bones": {
+                    "root": {
+                        "translate": [
+                            { "time": 0, "x": 0, "y": 0 },
+                            { "time": 1, "x": 8, "y": 0 }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let mut frames = Vec::new();
+    doc.for_each_frame("default", "move", 0.25, |time, sprites| {
+        frames.push((time, sprites[0].matrix.w.x));
+    }).unwrap();
+
+    assert!(frames.len() == 5);
+    assert!((frames[1].0 - 0.25).abs() < 0.001);
+    assert!((frames[1].1 - 2.0).abs() < 0.001);
+    assert!((frames[4].1 - 8.0).abs() < 0.001);
+
+    assert!(doc.for_each_frame("default", "run", 0.25, |_, _| ()).is_err());
+
+    for &delta in [0.0, -1.0, 1.0e-9, std::f32::NAN, std::f32::INFINITY].iter() {
+        match doc.for_each_frame("default", "move", delta, |_, _| panic!()) {
+            Err(spine::CalculationError::InvalidFrameRate) => (),
+            _ => panic!()
+        }
+    }
+}
+
+#[test]
+fn unknown_sequence_mode() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [ { "name": "fire", "bone": "root" } ],
+        "animations": {
+            "burn": {
+                "attachments": {
+                    "default": {
+                        "fire": {
+                            "flame": { "sequence": [ { "time": 0, "mode": "sideways" } ] }
+                        }
+                    }
+                }
+            }
+        }
+    }"#;
+
+    match spine::SpineDocument::new(BufReader::new(src)) {
+        Err(spine::LoadError::Format(_)) => (),
+        _ => panic!()
+    }
+}
+
+#[test]
+fn attachment_override_skins() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [ { "name": "hand", "bone": "root", "attachment": "fist" } ],
+        "skins": {
+            "default": { "hand": { "fist": { "width": 10, "height": 10 } } },
+            "gold": { "hand": { "sword": { "path": "gold-sword", "width": 10, "height": 40 } } },
+            "knight": { "hand": { "sword": { "width": 10, "height": 40 } } }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let mut options = spine::CalculationOptions::default();
+    options.attachment_overrides.insert("hand".to_string(), Some("sword".to_string()));
+    let results = doc.calculate_with_options("default", None, 0.0, &options).unwrap();
+    assert!(results.sprites[0].name == "gold-sword");
+
+    options.attachment_override_skins.insert("hand".to_string(), "knight".to_string());
+    let results = doc.calculate_with_options("default", None, 0.0, &options).unwrap();
+    assert!(results.sprites[0].name == "sword");
+
+    options.attachment_overrides.insert("hand".to_string(), Some("shield".to_string()));
+    match doc.calculate_with_options("default", None, 0.0, &options) {
+        Err(spine::CalculationError::OverrideAttachmentNotFound { skin, slot, attachment }) => {
+            assert!(skin == "knight" && slot == "hand" && attachment == "shield");
+        },
+        _ => panic!()
+    }
+    assert!(!doc.calculate_with_options("default", None, 0.0, &options).unwrap_err()
+                .is_document_error());
+
+    options.attachment_override_skins.insert("hand".to_string(), "silver".to_string());
+    match doc.calculate_with_options("default", None, 0.0, &options) {
+        Err(spine::CalculationError::SkinNotFound) => (),
+        _ => panic!()
+    }
+}
+
+#[test]
+fn summary() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" }, { "name": "arm", "parent": "root" } ],
+        "slots": [ { "name": "arm", "bone": "arm" } ],
+        "skins": { "default": {} },
+        "animations": {
+            "wave": {
+                "bones": {
+                    "arm": { "rotate": [ { "time": 0, "angle": 0 }, { "time": 2, "angle": 45 } ] }
+                },
+                "slots": { "arm": { "color": [ { "time": 0.5, "color": "ff0000ff" } ] } }
+            },
+            "idle": {}
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    assert!(doc.get_summary() == "2 bones, 1 slots, 1 skins, 2 animations\n\
+                                  animation `idle`: 0s, bones: [], slots: []\n\
+                                  animation `wave`: 2s, bones: [arm], slots: [arm]\n");
+}
+
+#[test]
+fn looping_timelines() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" }, { "name": "legs", "parent": "root" } ],
+        "animations": {
+            "walk_and_wave": {
+                "bones": {
+                    "root": {
+                        "translate": [
+                            { "time": 0, "x": 0, "y": 0 },
+                            { "time": 3, "x": 30, "y": 0 }
+                        ]
+                    },
+                    "legs": {
+                        "translate": [
+                            { "time": 0, "x": 0, "y": 0 },
+                            { "time": 1, "x": 0, "y": 10 }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let mut options = spine::CalculationOptions::default();
+    let bones = doc.calculate_local_bones_with_options(Some("walk_and_wave"), 1.5, &options)
+                   .unwrap();
+    assert!((bones[0].1.w.x - 15.0).abs() < 0.001);
+    assert!((bones[1].1.w.y - 10.0).abs() < 0.001);
+
+    options.looping_timelines.insert("legs".to_string());
+    let bones = doc.calculate_local_bones_with_options(Some("walk_and_wave"), 1.5, &options)
+                   .unwrap();
+    assert!((bones[0].1.w.x - 15.0).abs() < 0.001);
+    assert!((bones[1].1.w.y - 5.0).abs() < 0.001);
+}
+
+#[test]
+fn sample_timelines() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" }, { "name": "arm", "parent": "root", "x": 5 } ],
+        "slots": [ { "name": "arm", "bone": "arm", "color": "ff000080" } ],
+        "animations": {
+            "wave": {
+                "bones": {
+                    "arm": { "rotate": [ { "time": 0, "angle": 0 }, { "time": 1, "angle": 90 } ] }
+                },
+                "slots": {
+                    "arm": {
+                        "alpha": [ { "time": 0, "value": 1 } ],
+                        "attachment": [ { "time": 0.5, "name": null } ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let (position, rotation, scale) = doc.sample_bone_timeline("wave", "arm", 0.5).unwrap()
+                                         .unwrap();
+    assert!(position == (0.0, 0.0) && scale == (1.0, 1.0));
+    assert!((rotation - 45.0).abs() < 0.001);
+    assert!(doc.sample_bone_timeline("wave", "root", 0.5).unwrap().is_none());
+    assert!(doc.sample_bone_timeline("run", "arm", 0.5).is_err());
+
+    let (color, attachment) = doc.sample_slot_timeline("wave", "arm", 0.75).unwrap().unwrap();
+    let color = color.unwrap();
+    assert!(color.c.r == 255 && color.c.g == 0 && color.a == 255);
+    assert!(attachment == Some(None));
+}
+
+#[test]
+fn interpolate_frames() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [
+            { "name": "body", "bone": "root", "attachment": "image" },
+            { "name": "hat", "bone": "root" }
+        ],
+        "skins": {
+            "default": {
+                "body": { "image": { "width": 10, "height": 10 } },
+                "hat": { "hat": { "width": 10, "height": 10 } }
+            }
+        },
+        "animations": {
+            "spin": {
+                "bones": {
+                    "root": {
+                        "rotate": [ { "time": 0, "angle": 0 }, { "time": 1, "angle": 90 } ],
+                        "translate": [
+                            { "time": 0, "x": 0, "y": 0 },
+                            { "time": 1, "x": 10, "y": 0 }
+                        ]
+                    }
+                },
+                "slots": { "hat": { "attachment": [ { "time": 1, "name": "hat" } ] } }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+    let frames = doc.calculate_frames("default", "spin", 2).unwrap();
+
+    let length = |m: &cgmath::Matrix4<f32>| (m.x.x * m.x.x + m.x.y * m.x.y).sqrt();
+
+    let middle = frames[0].interpolate(&frames[1], 0.25);
+    assert!(middle.sprites.len() == 1);
+    let matrix = &middle.sprites[0].matrix;
+    assert!((matrix.x.y / matrix.x.x - (22.5f32).to_radians().tan()).abs() < 0.001);
+    assert!((length(matrix) - length(&frames[0].sprites[0].matrix)).abs() < 0.001);
+    assert!((matrix.w.x - 2.5).abs() < 0.001);
+
+    // the hat only appears in the nearest frame
+    assert!(frames[0].interpolate(&frames[1], 0.75).sprites.len() == 2);
+}
+
+#[test]
+fn flat_frames() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [
+            { "name": "body", "bone": "root", "attachment": "image" },
+            { "name": "hat", "bone": "root" }
+        ],
+        "skins": {
+            "default": {
+                "body": { "image": { "width": 10, "height": 10 } },
+                "hat": { "hat": { "width": 10, "height": 10 } }
+            }
+        },
+        "animations": {
+            "move": {
+                "bones": {
+                    "root": {
+                        "translate": [
+                            { "time": 0, "x": 0, "y": 0 },
+                            { "time": 1, "x": 8, "y": 0 }
+                        ]
+                    }
+                },
+                "slots": { "hat": { "attachment": [ { "time": 0.5, "name": "hat" } ] } }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let (sprites, ranges) = doc.calculate_flat_frames("default", "move", 4.0).unwrap();
+    assert!(ranges == [0 .. 1, 1 .. 2, 2 .. 4, 4 .. 6, 6 .. 8]);
+    assert!(sprites.len() == 8);
+
+    let frame = &sprites[ranges[3].clone()];
+    assert!(frame[1].name == "hat");
+    assert!((frame[0].matrix.w.x - 6.0).abs() < 0.001);
+
+    for &fps in [0.0, -4.0, 1.0e9, std::f32::NAN, std::f32::INFINITY].iter() {
+        match doc.calculate_flat_frames("default", "move", fps) {
+            Err(spine::CalculationError::InvalidFrameRate) => (),
+            _ => panic!()
+        }
+    }
+}
+
+#[test]
+fn bounding_box() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root", "x": 10 } ],
+        "slots": [ { "name": "hitbox", "bone": "root", "attachment": "box" } ],
+        "skins": {
+            "default": {
+                "hitbox": {
+                    "box": {
+                        "type": "boundingbox", "vertexCount": 3,
+                        "vertices": [ 0, 0, 4, 0, 0, 2 ]
+                    },
+                    "image": { "width": 10, "height": 10 }
+                }
+            }
+        },
+        "animations": {
+            "move": {
+                "bones": {
+                    "root": { "translate": [ { "time": 0, "x": 0, "y": 5 } ] }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let polygon = doc.calculate_bounding_box("default", Some("move"), 0.0, "hitbox", "box")
+                     .unwrap().unwrap();
+    assert!(polygon.len() == 3);
+    let expected = [(10.0, 5.0), (14.0, 5.0), (10.0, 7.0)];
+    for (p, e) in polygon.iter().zip(expected.iter()) {
+        assert!((p.0 - e.0).abs() < 0.001 && (p.1 - e.1).abs() < 0.001);
+    }
+
+    assert!(doc.calculate_bounding_box("default", None, 0.0, "hitbox", "image").unwrap()
+               .is_none());
+    assert!(doc.calculate_bounding_box("default", None, 0.0, "hitbox", "circle").is_err());
+}
+
+#[test]
+fn animation_handle() {
+    let src: &[u8] = include_bytes!("example.json");
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let handle = doc.get_animation_handle("walk").unwrap();
+    assert!(handle.get_name() == "walk");
+    assert!(Some(handle.get_duration()) == doc.get_animation_duration("walk"));
+
+    let elapsed = handle.get_duration() * 1.5;
+    let expected = doc.calculate("default", Some("walk"), elapsed).unwrap();
+    assert!(handle.calculate("default", elapsed).unwrap() == expected);
+
+    assert!(doc.get_animation_handle("fly").is_none());
+}
+
+#[test]
+fn retime() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "animations": {
+            "walk": {
+                "bones": {
+                    "root": {
+                        "translate": [
+                            { "time": 0, "x": 0, "y": 0 },
+                            { "time": 1, "x": 10, "y": 0 }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let mut options = spine::CalculationOptions::default();
+    options.duration = Some(2.0);
+
+    let bones = doc.calculate_bones_with_options(Some("walk"), 0.5, &options).unwrap();
+    assert!((bones[0].1.w.x - 2.5).abs() < 0.001);
+    let bones = doc.calculate_bones_with_options(Some("walk"), 3.0, &options).unwrap();
+    assert!((bones[0].1.w.x - 5.0).abs() < 0.001);
+
+    options.duration = Some(0.0);
+    assert!(doc.calculate_bones_with_options(Some("walk"), 0.5, &options).is_err());
+}
+
+#[test]
+fn setup_bones() {
+    let src: &[u8] = include_bytes!("example.json");
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let setup = doc.calculate_setup_bones();
+    let expected = doc.calculate_bones(None, 0.0).unwrap();
+    assert!(setup == expected);
+    assert!(!setup.is_empty());
+}
+
+#[test]
+fn global_alpha() {
+    let src: &[u8] = br#"{
+        "bones": [ { "name": "root" } ],
+        "slots": [ { "name": "slot", "bone": "root", "attachment": "image", "color": "FF808080" } ],
+        "skins": { "default": { "slot": { "image": { "width": 10, "height": 10 } } } }
+    }"#;
+    let doc = spine::SpineDocument::new(BufReader::new(src)).unwrap();
+
+    let mut options = spine::CalculationOptions::default();
+    options.alpha = Some(1.0);
+    let opaque = doc.calculate_with_options("default", None, 0.0, &options).unwrap();
+    assert!(opaque == doc.calculate("default", None, 0.0).unwrap());
+
+    options.alpha = Some(0.5);
+    let results = doc.calculate_with_options("default", None, 0.0, &options).unwrap();
+    let color = &results.sprites[0].color;
+    assert!(color.c.r == 255 && color.c.g == 128 && color.c.b == 128);
+    assert!(color.a == 64);
+
+    let colors = doc.calculate_colors_with_options(None, 0.0, &options).unwrap();
+    assert!(colors[0].1.a == 64);
+}